@@ -0,0 +1,87 @@
+use std::{collections::HashMap, time::Instant};
+
+use langite::{parse_file, Lexer, MemoryLoader, SourceMap, TokenKind};
+
+const PEEKS_PER_TOKEN: usize = 3;
+
+/// A file with `count` procedures in the style of `test.lang`.
+fn generate_source(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        source.push_str(&format!(
+            "\
+/// Computes something for the benchmark.
+proc compute_{i}(n: u64, ptr: ^u64) => u64 {{
+    var result: u64 <- 0x{i:X}
+    while n > 0 {{
+        if n % 2 == 0 {{
+            result <- result * 3 + n // even
+        }} else {{
+            result <- result - 1
+        }}
+        n <- n - 1
+    }}
+    ptr^ <- result
+    return compute_{i}(result, ptr) /* recursion */
+}}
+
+"
+        ));
+    }
+    source
+}
+
+fn measure(name: &str, bytes: usize, iterations: u32, mut run: impl FnMut()) {
+    run();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        run();
+    }
+    let elapsed = start.elapsed() / iterations;
+    let megabytes_per_second = bytes as f64 / elapsed.as_secs_f64() / 1_000_000.0;
+    println!("{name:>6}: {elapsed:>10.2?} per iteration, {megabytes_per_second:.1} MB/s");
+}
+
+fn main() {
+    let source = generate_source(5_000);
+    println!("{} bytes of generated source", source.len());
+
+    measure("lex", source.len(), 10, || {
+        let mut lexer = Lexer::new(&mut SourceMap::new(), "bench.lang", &source);
+        while lexer.next_token().unwrap().kind != TokenKind::EndOfFile {}
+    });
+    // the parser peeks at most tokens a few times before taking them, which used to clone the
+    // lexer and scan the token again on every peek rather than scanning it once
+    measure("peek", source.len(), 10, || {
+        let mut lexer = Lexer::new(&mut SourceMap::new(), "bench.lang", &source);
+        loop {
+            for _ in 0..PEEKS_PER_TOKEN {
+                lexer.peek_token().unwrap();
+            }
+            if lexer.next_token().unwrap().kind == TokenKind::EndOfFile {
+                break;
+            }
+        }
+    });
+    measure("relex", source.len(), 10, || {
+        let mut lexer = Lexer::new(&mut SourceMap::new(), "bench.lang", &source);
+        loop {
+            for _ in 0..PEEKS_PER_TOKEN {
+                lexer.clone().next_token().unwrap();
+            }
+            if lexer.next_token().unwrap().kind == TokenKind::EndOfFile {
+                break;
+            }
+        }
+    });
+    measure("parse", source.len(), 10, || {
+        parse_file(
+            "bench.lang",
+            &source,
+            &mut SourceMap::new(),
+            &mut HashMap::new(),
+            &MemoryLoader::new(),
+        )
+        .unwrap();
+    });
+}
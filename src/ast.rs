@@ -7,11 +7,12 @@ use std::{
 use derive_more::{Display, IsVariant};
 use enum_as_inner::EnumAsInner;
 
-use crate::{SourceLocation, SourceSpan, Type};
+use crate::{FileId, IntegerSuffix, SourceSpan, Type};
 
 #[derive(Clone, Debug, IsVariant, EnumAsInner)]
 pub enum Ast {
     File(Rc<AstFile>),
+    Import(Rc<AstImport>),
     Procedure(Rc<AstProcedure>),
     ProcedureType(Rc<AstProcedureType>),
     Parameter(Rc<AstParameter>),
@@ -19,7 +20,9 @@ pub enum Ast {
     LetDeclaration(Rc<AstLet>),
     VarDeclaration(Rc<AstVar>),
     Name(Rc<AstName>),
+    MemberAccess(Rc<AstMemberAccess>),
     Integer(Rc<AstInteger>),
+    String(Rc<AstString>),
     Call(Rc<AstCall>),
     Return(Rc<AstReturn>),
     Unary(Rc<AstUnary>),
@@ -29,6 +32,13 @@ pub enum Ast {
     Cast(Rc<AstCast>),
     Assign(Rc<AstAssign>),
     Builtin(Rc<AstBuiltin>),
+    StructType(Rc<AstStructType>),
+    StructLiteral(Rc<AstStructLiteral>),
+    ArrayType(Rc<AstArrayType>),
+    ArrayLiteral(Rc<AstArrayLiteral>),
+    Index(Rc<AstIndex>),
+    SliceType(Rc<AstSliceType>),
+    Slice(Rc<AstSlice>),
 }
 
 impl PartialEq for Ast {
@@ -41,6 +51,7 @@ impl Ast {
     pub fn get_type(&self) -> Option<Rc<Type>> {
         match self {
             Ast::File(file) => file.resolved_type.borrow().clone(),
+            Ast::Import(import) => import.resolved_type.borrow().clone(),
             Ast::Procedure(procedure) => procedure.resolved_type.borrow().clone(),
             Ast::ProcedureType(procedure_type) => procedure_type.resolved_type.borrow().clone(),
             Ast::Parameter(parameter) => parameter.resolved_type.borrow().clone(),
@@ -51,9 +62,14 @@ impl Ast {
                 .resolved_declaration
                 .borrow()
                 .as_ref()
-                .map(Ast::get_type)
-                .flatten(),
+                .and_then(Ast::get_type),
+            Ast::MemberAccess(member_access) => match &*member_access.resolved_declaration.borrow()
+            {
+                Some(declaration) => declaration.get_type(),
+                None => member_access.resolved_type.borrow().clone(),
+            },
             Ast::Integer(integer) => integer.resolved_type.borrow().clone(),
+            Ast::String(string) => string.resolved_type.borrow().clone(),
             Ast::Call(call) => call.resolved_type.borrow().clone(),
             Ast::Return(returnn) => returnn.resolved_type.borrow().clone(),
             Ast::Unary(unary) => unary.resolved_type.borrow().clone(),
@@ -66,16 +82,23 @@ impl Ast {
                 AstBuiltinKind::Bool
                 | AstBuiltinKind::Type
                 | AstBuiltinKind::Void
-                | AstBuiltinKind::IntegerType { size: _, signed: _ } => {
-                    builtin.resolved_type.borrow().clone()
-                }
+                | AstBuiltinKind::IntegerType { size: _, signed: _ }
+                | AstBuiltinKind::String => builtin.resolved_type.borrow().clone(),
             },
+            Ast::StructType(struct_type) => struct_type.resolved_type.borrow().clone(),
+            Ast::StructLiteral(literal) => literal.resolved_type.borrow().clone(),
+            Ast::ArrayType(array_type) => array_type.resolved_type.borrow().clone(),
+            Ast::ArrayLiteral(literal) => literal.resolved_type.borrow().clone(),
+            Ast::Index(index) => index.resolved_type.borrow().clone(),
+            Ast::SliceType(slice_type) => slice_type.resolved_type.borrow().clone(),
+            Ast::Slice(slice) => slice.resolved_type.borrow().clone(),
         }
     }
 
     pub fn set_resolving(&self, value: bool) {
         match self {
             Ast::File(file) => file.resolving.set(value),
+            Ast::Import(import) => import.resolving.set(value),
             Ast::Procedure(procedure) => procedure.resolving.set(value),
             Ast::ProcedureType(procedure_type) => procedure_type.resolving.set(value),
             Ast::Parameter(parameter) => parameter.resolving.set(value),
@@ -83,7 +106,9 @@ impl Ast {
             Ast::LetDeclaration(declaration) => declaration.resolving.set(value),
             Ast::VarDeclaration(declaration) => declaration.resolving.set(value),
             Ast::Name(name) => name.resolving.set(value),
+            Ast::MemberAccess(member_access) => member_access.resolving.set(value),
             Ast::Integer(integer) => integer.resolving.set(value),
+            Ast::String(string) => string.resolving.set(value),
             Ast::Call(call) => call.resolving.set(value),
             Ast::Return(returnn) => returnn.resolving.set(value),
             Ast::Unary(unary) => unary.resolving.set(value),
@@ -97,13 +122,22 @@ impl Ast {
                 AstBuiltinKind::Void => (),
                 AstBuiltinKind::Bool => (),
                 AstBuiltinKind::IntegerType { size: _, signed: _ } => (),
+                AstBuiltinKind::String => (),
             },
+            Ast::StructType(struct_type) => struct_type.resolving.set(value),
+            Ast::StructLiteral(literal) => literal.resolving.set(value),
+            Ast::ArrayType(array_type) => array_type.resolving.set(value),
+            Ast::ArrayLiteral(literal) => literal.resolving.set(value),
+            Ast::Index(index) => index.resolving.set(value),
+            Ast::SliceType(slice_type) => slice_type.resolving.set(value),
+            Ast::Slice(slice) => slice.resolving.set(value),
         }
     }
 
     pub fn get_resolving(&self) -> bool {
         match self {
             Ast::File(file) => file.resolving.get(),
+            Ast::Import(import) => import.resolving.get(),
             Ast::Procedure(procedure) => procedure.resolving.get(),
             Ast::ProcedureType(procedure_type) => procedure_type.resolving.get(),
             Ast::Parameter(parameter) => parameter.resolving.get(),
@@ -111,7 +145,9 @@ impl Ast {
             Ast::LetDeclaration(declaration) => declaration.resolving.get(),
             Ast::VarDeclaration(declaration) => declaration.resolving.get(),
             Ast::Name(name) => name.resolving.get(),
+            Ast::MemberAccess(member_access) => member_access.resolving.get(),
             Ast::Integer(integer) => integer.resolving.get(),
+            Ast::String(string) => string.resolving.get(),
             Ast::Call(call) => call.resolving.get(),
             Ast::Return(returnn) => returnn.resolving.get(),
             Ast::Unary(unary) => unary.resolving.get(),
@@ -125,48 +161,59 @@ impl Ast {
                 AstBuiltinKind::Void => false,
                 AstBuiltinKind::Bool => false,
                 AstBuiltinKind::IntegerType { size: _, signed: _ } => false,
+                AstBuiltinKind::String => false,
             },
+            Ast::StructType(struct_type) => struct_type.resolving.get(),
+            Ast::StructLiteral(literal) => literal.resolving.get(),
+            Ast::ArrayType(array_type) => array_type.resolving.get(),
+            Ast::ArrayLiteral(literal) => literal.resolving.get(),
+            Ast::Index(index) => index.resolving.get(),
+            Ast::SliceType(slice_type) => slice_type.resolving.get(),
+            Ast::Slice(slice) => slice.resolving.get(),
         }
     }
 
     pub fn get_location(&self) -> SourceSpan {
         match self {
-            Ast::File(file) => file.location.clone(),
-            Ast::Procedure(procedure) => procedure.location.clone(),
-            Ast::ProcedureType(procedure_type) => procedure_type.location.clone(),
-            Ast::Parameter(parameter) => parameter.location.clone(),
-            Ast::Scope(scope) => scope.location.clone(),
-            Ast::LetDeclaration(declaration) => declaration.location.clone(),
-            Ast::VarDeclaration(declaration) => declaration.location.clone(),
-            Ast::Name(name) => name.location.clone(),
-            Ast::Integer(integer) => integer.location.clone(),
-            Ast::Call(call) => call.location.clone(),
-            Ast::Return(returnn) => returnn.location.clone(),
-            Ast::Unary(unary) => unary.location.clone(),
-            Ast::Binary(binary) => binary.location.clone(),
-            Ast::If(iff) => iff.location.clone(),
-            Ast::While(whilee) => whilee.location.clone(),
-            Ast::Cast(cast) => cast.location.clone(),
-            Ast::Assign(assign) => assign.location.clone(),
+            Ast::File(file) => file.location,
+            Ast::Import(import) => import.location,
+            Ast::Procedure(procedure) => procedure.location,
+            Ast::ProcedureType(procedure_type) => procedure_type.location,
+            Ast::Parameter(parameter) => parameter.location,
+            Ast::Scope(scope) => scope.location,
+            Ast::LetDeclaration(declaration) => declaration.location,
+            Ast::VarDeclaration(declaration) => declaration.location,
+            Ast::Name(name) => name.location,
+            Ast::MemberAccess(member_access) => member_access.location,
+            Ast::Integer(integer) => integer.location,
+            Ast::String(string) => string.location,
+            Ast::Call(call) => call.location,
+            Ast::Return(returnn) => returnn.location,
+            Ast::Unary(unary) => unary.location,
+            Ast::Binary(binary) => binary.location,
+            Ast::If(iff) => iff.location,
+            Ast::While(whilee) => whilee.location,
+            Ast::Cast(cast) => cast.location,
+            Ast::Assign(assign) => assign.location,
             Ast::Builtin(_) => SourceSpan {
-                filepath: "builtin.lang".into(),
-                start: SourceLocation {
-                    position: 0,
-                    line: 1,
-                    column: 1,
-                },
-                end: SourceLocation {
-                    position: 0,
-                    line: 1,
-                    column: 1,
-                },
+                file: FileId::BUILTIN,
+                start: 0,
+                end: 0,
             },
+            Ast::StructType(struct_type) => struct_type.location,
+            Ast::StructLiteral(literal) => literal.location,
+            Ast::ArrayType(array_type) => array_type.location,
+            Ast::ArrayLiteral(literal) => literal.location,
+            Ast::Index(index) => index.location,
+            Ast::SliceType(slice_type) => slice_type.location,
+            Ast::Slice(slice) => slice.location,
         }
     }
 
     pub(crate) fn get_ptr(&self) -> *const c_void {
         match self {
             Ast::File(file) => Rc::as_ptr(file) as *const _,
+            Ast::Import(import) => Rc::as_ptr(import) as *const _,
             Ast::Procedure(procedure) => Rc::as_ptr(procedure) as *const _,
             Ast::ProcedureType(procedure_type) => Rc::as_ptr(procedure_type) as *const _,
             Ast::Parameter(parameter) => Rc::as_ptr(parameter) as *const _,
@@ -174,7 +221,9 @@ impl Ast {
             Ast::LetDeclaration(declaration) => Rc::as_ptr(declaration) as *const _,
             Ast::VarDeclaration(declaration) => Rc::as_ptr(declaration) as *const _,
             Ast::Name(name) => Rc::as_ptr(name) as *const _,
+            Ast::MemberAccess(member_access) => Rc::as_ptr(member_access) as *const _,
             Ast::Integer(integer) => Rc::as_ptr(integer) as *const _,
+            Ast::String(string) => Rc::as_ptr(string) as *const _,
             Ast::Call(call) => Rc::as_ptr(call) as *const _,
             Ast::Return(returnn) => Rc::as_ptr(returnn) as *const _,
             Ast::Unary(unary) => Rc::as_ptr(unary) as *const _,
@@ -184,6 +233,13 @@ impl Ast {
             Ast::Cast(cast) => Rc::as_ptr(cast) as *const _,
             Ast::Assign(assign) => Rc::as_ptr(assign) as *const _,
             Ast::Builtin(builtin) => Rc::as_ptr(builtin) as *const _,
+            Ast::StructType(struct_type) => Rc::as_ptr(struct_type) as *const _,
+            Ast::StructLiteral(literal) => Rc::as_ptr(literal) as *const _,
+            Ast::ArrayType(array_type) => Rc::as_ptr(array_type) as *const _,
+            Ast::ArrayLiteral(literal) => Rc::as_ptr(literal) as *const _,
+            Ast::Index(index) => Rc::as_ptr(index) as *const _,
+            Ast::SliceType(slice_type) => Rc::as_ptr(slice_type) as *const _,
+            Ast::Slice(slice) => Rc::as_ptr(slice) as *const _,
         }
     }
 }
@@ -196,6 +252,32 @@ pub struct AstFile {
     pub resolved_type: ResolvedType,
     pub location: SourceSpan,
     pub expressions: Vec<Ast>,
+    /// A file imported from several places only has its names resolved once.
+    pub names_resolved: Cell<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, EnumAsInner)]
+pub enum AstImportKind {
+    /// `#import "x.lang"` brings every declaration of the file into scope.
+    All,
+    /// `#import "x.lang" as x` declares a module `x` whose members are used as `x.name`.
+    Module(String),
+    /// `#import "x.lang" (a, b)` brings only the listed declarations into scope.
+    Names(Vec<(String, SourceSpan)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstImport {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    /// The path as written after `#import`.
+    pub path: String,
+    pub file: Rc<AstFile>,
+    pub kind: AstImportKind,
+    /// Only the first import of a file runs its top level code, later imports of the same
+    /// file just bring its names into scope.
+    pub is_first: bool,
 }
 
 #[derive(Clone, Debug, Display, PartialEq, EnumAsInner)]
@@ -213,6 +295,7 @@ pub struct AstProcedure {
     pub resolving: Cell<bool>,
     pub resolved_type: ResolvedType,
     pub location: SourceSpan,
+    pub public: bool,
     pub name: String,
     pub parameters: Vec<Rc<AstParameter>>,
     pub return_type: Ast,
@@ -259,6 +342,7 @@ pub struct AstLet {
     pub resolving: Cell<bool>,
     pub resolved_type: ResolvedType,
     pub location: SourceSpan,
+    pub public: bool,
     pub name: String,
     pub typ: Option<Ast>,
     pub value: Ast,
@@ -269,6 +353,7 @@ pub struct AstVar {
     pub resolving: Cell<bool>,
     pub resolved_type: ResolvedType,
     pub location: SourceSpan,
+    pub public: bool,
     pub name: String,
     pub typ: Option<Ast>,
     pub value: Ast,
@@ -282,12 +367,37 @@ pub struct AstName {
     pub resolved_declaration: RefCell<Option<Ast>>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstMemberAccess {
+    pub resolving: Cell<bool>,
+    /// Only set when accessing a field, a module member has the type of its declaration.
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub operand: Ast,
+    pub name: String,
+    /// The declaration for a member of a module, `None` for the field of a struct.
+    pub resolved_declaration: RefCell<Option<Ast>>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct AstInteger {
     pub resolving: Cell<bool>,
     pub resolved_type: ResolvedType,
     pub location: SourceSpan,
     pub value: u128,
+    pub suffix: Option<IntegerSuffix>,
+    /// Written as a character literal like `'a'`, which is a `u8` unless another integer
+    /// type is expected.
+    pub character: bool,
+}
+
+/// `"text"`, a `string` that points to static, zero terminated data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstString {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub value: String,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -408,5 +518,90 @@ pub enum AstBuiltinKind {
     Type,
     Void,
     Bool,
-    IntegerType { size: usize, signed: bool },
+    IntegerType {
+        size: usize,
+        signed: bool,
+    },
+    /// `[]u8`
+    String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStructType {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    /// The struct type itself, the type of the expression is `type`.
+    pub typ: RefCell<Option<Rc<Type>>>,
+    pub location: SourceSpan,
+    pub fields: Vec<AstStructField>,
+}
+
+/// A field of a struct type like `x: s32`, or of a struct literal like `x = 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStructField {
+    pub location: SourceSpan,
+    pub name: String,
+    pub value: Ast,
+}
+
+/// `T.{ x = 1, y = 2 }`, every field of the struct type `T` is given exactly once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstStructLiteral {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub typ: Ast,
+    pub fields: Vec<AstStructField>,
+}
+
+/// `[N]T`, the length has to be a constant integer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstArrayType {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    /// The array type itself, the type of the expression is `type`.
+    pub typ: RefCell<Option<Rc<Type>>>,
+    pub location: SourceSpan,
+    pub length: Ast,
+    pub element_type: Ast,
+}
+
+/// `.[a, b, c]`, the element type comes from the suggested type or the first element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstArrayLiteral {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub elements: Vec<Ast>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstIndex {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub operand: Ast,
+    pub index: Ast,
+}
+
+/// `[]T`, a pointer to the first element together with the number of elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstSliceType {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    /// The slice type itself, the type of the expression is `type`.
+    pub typ: RefCell<Option<Rc<Type>>>,
+    pub location: SourceSpan,
+    pub element_type: Ast,
+}
+
+/// `a[start..end]` of an array or a slice, `end` is exclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AstSlice {
+    pub resolving: Cell<bool>,
+    pub resolved_type: ResolvedType,
+    pub location: SourceSpan,
+    pub operand: Ast,
+    pub start: Ast,
+    pub end: Ast,
 }
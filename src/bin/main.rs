@@ -1,183 +1,348 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{fmt::Display, path::PathBuf, process::ExitCode};
 
 use langite::*;
 
-fn unwrap_error<T, E: Display>(result: Result<T, E>) -> T {
-    result.unwrap_or_else(|error| {
-        eprintln!("{}", error);
-        std::process::exit(1)
-    })
+const EXIT_USAGE: u8 = 1;
+const EXIT_IO: u8 = 2;
+const EXIT_LEXING: u8 = 3;
+const EXIT_PARSING: u8 = 4;
+const EXIT_RESOLVING: u8 = 5;
+const EXIT_UNFORMATTED: u8 = 6;
+
+const USAGE: &str = "\
+Usage: langite [options] <input>...
+       langite fmt [--check] <input>...
+
+Commands:
+    fmt               Format the inputs in place, with --check only list the
+                      inputs that are not formatted and fail if there are any
+
+Options:
+    -I <directory>    Also look for #import files in <directory>, searched
+                      before the directories in $LANGITE_PATH
+    -o <path>         Write the output to <path>, '-' means stdout
+                      (default: 'output.c' for --emit c, stdout otherwise)
+    --emit <stage>    Stop after <stage> and print its result,
+                      one of 'tokens', 'ast' or 'c' (default: 'c')
+    --bounds-checks   Make the C output trap on out of bounds indices
+                      and slices
+    -h, --help        Print this message";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Emit {
+    Tokens,
+    Ast,
+    C,
 }
 
-fn main() {
-    let filepath = "./test.lang";
-    let file = unwrap_error(parse_file(
-        filepath.into(),
-        &std::fs::read_to_string(filepath).unwrap(),
-        &mut Default::default(),
-    ));
-    let program = Ast::File(file);
-    let mut names = HashMap::from([
-        (
-            "type".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::Type,
-                }
-                .into(),
-            ),
-        ),
-        (
-            "void".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::Void,
-                }
-                .into(),
-            ),
-        ),
-        (
-            "bool".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::Bool,
-                }
-                .into(),
-            ),
-        ),
-        (
-            "s8".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 1,
-                        signed: true,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "s16".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 2,
-                        signed: true,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "s32".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 4,
-                        signed: true,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "s64".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 8,
-                        signed: true,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "u8".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 1,
-                        signed: false,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "u16".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 2,
-                        signed: false,
-                    },
-                }
-                .into(),
-            ),
-        ),
-        (
-            "u32".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 4,
-                        signed: false,
-                    },
+struct Options {
+    inputs: Vec<String>,
+    search_paths: Vec<PathBuf>,
+    output: Option<String>,
+    emit: Emit,
+    bounds_checks: bool,
+}
+
+struct FormatOptions {
+    inputs: Vec<String>,
+    check: bool,
+}
+
+struct Failure {
+    code: u8,
+    message: String,
+    diagnostics: Vec<Diagnostic>,
+    source_map: SourceMap,
+}
+
+impl Failure {
+    fn new(code: u8, message: impl Display) -> Failure {
+        Failure {
+            code,
+            message: message.to_string(),
+            diagnostics: vec![],
+            source_map: SourceMap::new(),
+        }
+    }
+
+    fn with_diagnostics(mut self, diagnostics: Vec<Diagnostic>, source_map: &SourceMap) -> Failure {
+        self.diagnostics = diagnostics;
+        self.source_map = source_map.clone();
+        self
+    }
+
+    fn compiling(error: CompileError, source_map: &SourceMap) -> Failure {
+        let code = match &error {
+            // the first error decides, later ones may only be caused by it
+            CompileError::Parsing(errors) => match errors.first() {
+                Some(ParsingError::LexerError(_)) => EXIT_LEXING,
+                Some(ParsingError::UnableToReadFile { .. }) => EXIT_IO,
+                _ => EXIT_PARSING,
+            },
+            CompileError::Resolving(_) => EXIT_RESOLVING,
+            CompileError::UnableToReadInput { .. } | CompileError::Io(_) => EXIT_IO,
+            CompileError::NoInputFiles => EXIT_USAGE,
+        };
+        let diagnostics = error.to_diagnostics();
+        Failure::new(code, error).with_diagnostics(diagnostics, source_map)
+    }
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, Failure> {
+    let mut inputs = vec![];
+    let mut search_paths = vec![];
+    let mut output = None;
+    let mut emit = Emit::C;
+    let mut bounds_checks = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-I" => {
+                search_paths.push(
+                    args.next()
+                        .ok_or_else(|| Failure::new(EXIT_USAGE, "Expected a directory after '-I'"))?
+                        .into(),
+                );
+            }
+            "-o" => {
+                output = Some(
+                    args.next()
+                        .ok_or_else(|| Failure::new(EXIT_USAGE, "Expected a path after '-o'"))?,
+                );
+            }
+            "--emit" => {
+                let stage = args
+                    .next()
+                    .ok_or_else(|| Failure::new(EXIT_USAGE, "Expected a stage after '--emit'"))?;
+                emit = match stage.as_str() {
+                    "tokens" => Emit::Tokens,
+                    "ast" => Emit::Ast,
+                    "c" => Emit::C,
+                    _ => {
+                        return Err(Failure::new(
+                            EXIT_USAGE,
+                            format!("Unknown stage '{stage}', expected 'tokens', 'ast' or 'c'"),
+                        ))
+                    }
+                };
+            }
+            "--bounds-checks" => bounds_checks = true,
+            _ if arg.starts_with('-') => {
+                return Err(Failure::new(EXIT_USAGE, format!("Unknown option '{arg}'")))
+            }
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        return Err(Failure::new(EXIT_USAGE, "No input files"));
+    }
+    if let Some(paths) = std::env::var_os("LANGITE_PATH") {
+        search_paths.extend(std::env::split_paths(&paths));
+    }
+    Ok(Some(Options {
+        inputs,
+        search_paths,
+        output,
+        emit,
+        bounds_checks,
+    }))
+}
+
+fn parse_format_options(
+    args: impl Iterator<Item = String>,
+) -> Result<Option<FormatOptions>, Failure> {
+    let mut inputs = vec![];
+    let mut check = false;
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--check" => check = true,
+            _ if arg.starts_with('-') => {
+                return Err(Failure::new(EXIT_USAGE, format!("Unknown option '{arg}'")))
+            }
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        return Err(Failure::new(EXIT_USAGE, "No input files"));
+    }
+    Ok(Some(FormatOptions { inputs, check }))
+}
+
+fn format_files(options: &FormatOptions) -> Result<(), Failure> {
+    let mut unformatted = 0;
+    for filepath in &options.inputs {
+        let source = read_source(filepath)?;
+        let mut source_map = SourceMap::new();
+        let formatted = format_source(filepath, &source, &mut source_map)
+            .map_err(|errors| Failure::compiling(errors.into(), &source_map))?;
+        if formatted == source {
+            continue;
+        }
+        if options.check {
+            println!("{filepath}");
+            unformatted += 1;
+        } else {
+            std::fs::write(filepath, formatted).map_err(|error| {
+                Failure::new(EXIT_IO, format!("Unable to write '{filepath}': {error}"))
+            })?;
+        }
+    }
+    if unformatted == 0 {
+        Ok(())
+    } else {
+        Err(Failure::new(
+            EXIT_UNFORMATTED,
+            format!("{unformatted} file(s) are not formatted"),
+        ))
+    }
+}
+
+fn read_source(filepath: &str) -> Result<String, Failure> {
+    std::fs::read_to_string(filepath)
+        .map_err(|error| Failure::new(EXIT_IO, format!("Unable to read '{filepath}': {error}")))
+}
+
+/// Writes one line per token with its span, kind and data, lexer errors are written in
+/// place of the token and lexing carries on after them.
+fn dump_tokens(
+    inputs: &[String],
+    source_map: &mut SourceMap,
+    stream: &mut dyn std::io::Write,
+) -> Result<Vec<LexerError>, Failure> {
+    let mut errors = vec![];
+    for filepath in inputs {
+        let source = read_source(filepath)?;
+        let mut lexer = Lexer::new(source_map, filepath, &source);
+        lexer.doc_comments = true;
+        for token in lexer {
+            let line = match &token {
+                Ok(token) if token.data == TokenData::None => {
+                    format!(
+                        "{} {:?}",
+                        format_span(source_map, &token.location),
+                        token.kind
+                    )
                 }
-                .into(),
-            ),
-        ),
-        (
-            "u64".into(),
-            Declaration::Builtin(
-                AstBuiltin {
-                    resolving: false.into(),
-                    resolved_type: None.into(),
-                    typ: None.into(),
-                    kind: AstBuiltinKind::IntegerType {
-                        size: 8,
-                        signed: false,
-                    },
+                Ok(token) => format!(
+                    "{} {:?} {}",
+                    format_span(source_map, &token.location),
+                    token.kind,
+                    token.data
+                ),
+                Err(error) => {
+                    let diagnostic = error.to_diagnostic();
+                    format!(
+                        "{} error: {}",
+                        format_span(source_map, &diagnostic.location),
+                        diagnostic.message
+                    )
                 }
-                .into(),
-            ),
-        ),
-    ]);
-    unwrap_error(resolve_names(&program, &mut names));
-    unwrap_error(resolve(&program, None, &mut vec![], &None, &mut vec![]));
-    let mut string = Vec::new();
-    emit(&program, &mut 1, &mut string).unwrap();
-    std::fs::write("output.c", &string).unwrap();
+            };
+            writeln!(stream, "{line}").map_err(|error| Failure::new(EXIT_IO, error))?;
+            errors.extend(token.err());
+        }
+    }
+    Ok(errors)
+}
+
+fn format_span(source_map: &SourceMap, span: &SourceSpan) -> String {
+    format!(
+        "{}:{}-{}",
+        source_map.filepath(span),
+        source_map.start_location(span),
+        source_map.end_location(span)
+    )
+}
+
+fn new_compiler(options: &Options) -> Compiler {
+    let mut compiler = Compiler::new();
+    compiler
+        .set_loader(FileSystemLoader {
+            search_paths: options.search_paths.clone(),
+        })
+        .set_bounds_checks(options.bounds_checks);
+    compiler
+}
+
+fn run(options: &Options) -> Result<(), Failure> {
+    let mut output = Vec::new();
+    let mut lexer_errors = vec![];
+    let mut source_map = SourceMap::new();
+    match options.emit {
+        Emit::Tokens => {
+            lexer_errors = dump_tokens(&options.inputs, &mut source_map, &mut output)?;
+        }
+        Emit::Ast => {
+            let mut compiler = new_compiler(options);
+            let program = compiler
+                .parse_paths(&options.inputs)
+                .map_err(|error| Failure::compiling(error, compiler.source_map()))?;
+            output = program.to_string().into_bytes();
+        }
+        Emit::C => {
+            let mut compiler = new_compiler(options);
+            let program = compiler
+                .parse_paths(&options.inputs)
+                .map_err(|error| Failure::compiling(error, compiler.source_map()))?;
+            output = compiler
+                .compile_program(&program)
+                .map_err(|error| Failure::compiling(error, compiler.source_map()))?;
+        }
+    }
+    let output_path = match (&options.output, options.emit) {
+        (Some(path), _) => path.as_str(),
+        (None, Emit::C) => "output.c",
+        (None, _) => "-",
+    };
+    if output_path == "-" {
+        use std::io::Write;
+        std::io::stdout().write_all(&output)
+    } else {
+        std::fs::write(output_path, &output)
+    }
+    .map_err(|error| Failure::new(EXIT_IO, format!("Unable to write '{output_path}': {error}")))?;
+    match lexer_errors.first() {
+        Some(error) => Err(Failure::new(EXIT_LEXING, error).with_diagnostics(
+            lexer_errors.iter().map(LexerError::to_diagnostic).collect(),
+            &source_map,
+        )),
+        None => Ok(()),
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    let result = if args.peek().map(String::as_str) == Some("fmt") {
+        parse_format_options(args.skip(1))
+            .map(|options| options.map(|options| format_files(&options)))
+    } else {
+        parse_options(args).map(|options| options.map(|options| run(&options)))
+    }
+    .and_then(|result| match result {
+        Some(result) => result,
+        None => {
+            println!("{USAGE}");
+            Ok(())
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            let rendered = !failure.diagnostics.is_empty()
+                && failure.diagnostics.iter().all(|diagnostic| {
+                    diagnostic
+                        .render(&failure.source_map, &mut std::io::stderr())
+                        .is_ok()
+                });
+            if !rendered {
+                eprintln!("{}", failure.message);
+            }
+            if failure.code == EXIT_USAGE {
+                eprintln!();
+                eprintln!("{USAGE}");
+            }
+            ExitCode::from(failure.code)
+        }
+    }
 }
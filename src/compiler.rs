@@ -0,0 +1,286 @@
+use std::{collections::HashMap, rc::Rc};
+
+use derive_more::Display;
+use enum_as_inner::EnumAsInner;
+
+use crate::{
+    emit, parse_file, resolve, resolve_names, Ast, AstFile, Declaration, Diagnostic,
+    FileSystemLoader, ParsingError, Prelude, ResolvingError, SourceLoader, SourceMap, Type,
+};
+
+#[derive(Debug, Display, EnumAsInner)]
+pub enum CompileError {
+    #[display(
+        fmt = "{}",
+        "_0.iter().map(ToString::to_string).collect::<Vec<_>>().join(\"\\n\")"
+    )]
+    Parsing(Vec<ParsingError>),
+    #[display(fmt = "{}", _0)]
+    Resolving(ResolvingError),
+    #[display(fmt = "Unable to read '{filepath}': {error}")]
+    UnableToReadInput {
+        filepath: String,
+        error: std::io::Error,
+    },
+    #[display(fmt = "{}", _0)]
+    Io(std::io::Error),
+    #[display(fmt = "No input files")]
+    NoInputFiles,
+}
+
+impl CompileError {
+    /// The diagnostics for errors that point into the source, errors from reading or
+    /// writing the inputs and outputs themselves have no location and so no diagnostics.
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            CompileError::Parsing(errors) => {
+                errors.iter().map(ParsingError::to_diagnostic).collect()
+            }
+            CompileError::Resolving(error) => vec![error.to_diagnostic()],
+            CompileError::UnableToReadInput { .. }
+            | CompileError::Io(_)
+            | CompileError::NoInputFiles => vec![],
+        }
+    }
+}
+
+impl From<Vec<ParsingError>> for CompileError {
+    fn from(errors: Vec<ParsingError>) -> CompileError {
+        CompileError::Parsing(errors)
+    }
+}
+
+impl From<ResolvingError> for CompileError {
+    fn from(error: ResolvingError) -> CompileError {
+        CompileError::Resolving(error)
+    }
+}
+
+impl From<std::io::Error> for CompileError {
+    fn from(error: std::io::Error) -> CompileError {
+        CompileError::Io(error)
+    }
+}
+
+/// Owns the state that is shared by every stage of compiling one program.
+///
+/// The type cache and the name table belong together, the builtins in the name table
+/// remember the types they resolved to, so a `Compiler` should be used for a single
+/// program and a new one created for the next.
+pub struct Compiler {
+    names: HashMap<String, Declaration>,
+    imported_files: HashMap<String, Option<Rc<AstFile>>>,
+    loader: Box<dyn SourceLoader>,
+    type_cache: Vec<Rc<Type>>,
+    source_map: SourceMap,
+    bounds_checks: bool,
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler::with_prelude(&Prelude::standard())
+    }
+
+    pub fn with_prelude(prelude: &Prelude) -> Compiler {
+        Compiler {
+            names: prelude.names(),
+            imported_files: HashMap::new(),
+            loader: Box::new(FileSystemLoader::new()),
+            type_cache: vec![],
+            source_map: SourceMap::new(),
+            bounds_checks: false,
+        }
+    }
+
+    /// Replaces the loader that input files and imports are read with, which is a
+    /// [`FileSystemLoader`] without search paths by default.
+    pub fn set_loader(&mut self, loader: impl SourceLoader + 'static) -> &mut Compiler {
+        self.loader = Box::new(loader);
+        self
+    }
+
+    /// Makes the emitted C trap when an index or slice is out of bounds, without it the
+    /// checks are only compiled in when `LANGITE_BOUNDS_CHECKS` is defined.
+    pub fn set_bounds_checks(&mut self, enabled: bool) -> &mut Compiler {
+        self.bounds_checks = enabled;
+        self
+    }
+
+    pub fn loader(&self) -> &dyn SourceLoader {
+        self.loader.as_ref()
+    }
+
+    pub fn type_cache(&self) -> &[Rc<Type>] {
+        &self.type_cache
+    }
+
+    /// Every file parsed so far, which the spans in errors and diagnostics point into.
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    pub fn parse_source(
+        &mut self,
+        filepath: &str,
+        source: &str,
+    ) -> Result<Rc<AstFile>, CompileError> {
+        Ok(parse_file(
+            filepath,
+            source,
+            &mut self.source_map,
+            &mut self.imported_files,
+            self.loader.as_ref(),
+        )?)
+    }
+
+    pub fn parse_path(&mut self, filepath: &str) -> Result<Rc<AstFile>, CompileError> {
+        let source =
+            self.loader
+                .load(filepath)
+                .map_err(|error| CompileError::UnableToReadInput {
+                    filepath: filepath.into(),
+                    error,
+                })?;
+        self.parse_source(filepath, &source)
+    }
+
+    /// Parses every file and joins them into one program, in the order they were given.
+    pub fn parse_paths(&mut self, filepaths: &[String]) -> Result<Ast, CompileError> {
+        if filepaths.is_empty() {
+            return Err(CompileError::NoInputFiles);
+        }
+        let mut files = filepaths
+            .iter()
+            .map(|filepath| self.parse_path(filepath))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Ast::File(if files.len() == 1 {
+            files.pop().unwrap()
+        } else {
+            AstFile {
+                resolving: false.into(),
+                resolved_type: None.into(),
+                location: files[0].location,
+                expressions: files
+                    .iter()
+                    .flat_map(|file| file.expressions.iter().cloned())
+                    .collect(),
+                names_resolved: false.into(),
+            }
+            .into()
+        }))
+    }
+
+    /// Resolves the names and types of a parsed program.
+    pub fn check(&mut self, program: &Ast) -> Result<(), CompileError> {
+        resolve_names(program, &mut self.names)?;
+        resolve(program, None, &mut vec![], &None, &mut self.type_cache)?;
+        Ok(())
+    }
+
+    /// Writes the C source of a checked program.
+    pub fn emit_c(
+        &self,
+        program: &Ast,
+        stream: &mut dyn std::io::Write,
+    ) -> Result<(), CompileError> {
+        if self.bounds_checks {
+            writeln!(stream, "#define LANGITE_BOUNDS_CHECKS")?;
+        }
+        emit(program, &self.type_cache, &self.source_map, &mut 1, stream)?;
+        Ok(())
+    }
+
+    pub fn compile_program(&mut self, program: &Ast) -> Result<Vec<u8>, CompileError> {
+        self.check(program)?;
+        let mut output = vec![];
+        self.emit_c(program, &mut output)?;
+        Ok(output)
+    }
+
+    pub fn compile_source(
+        &mut self,
+        filepath: &str,
+        source: &str,
+    ) -> Result<Vec<u8>, CompileError> {
+        let program = Ast::File(self.parse_source(filepath, source)?);
+        self.compile_program(&program)
+    }
+
+    pub fn compile_path(&mut self, filepath: &str) -> Result<Vec<u8>, CompileError> {
+        let program = Ast::File(self.parse_path(filepath)?);
+        self.compile_program(&program)
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Compiler {
+        Compiler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CompileError, Compiler, MemoryLoader};
+
+    fn compiler() -> Compiler {
+        let mut loader = MemoryLoader::new();
+        loader
+            .add_file("a.lang", "var a: u64 <- 1\n")
+            .add_file("b.lang", "#import \"lib/c.lang\"\nvar b: u64 <- a + c\n")
+            .add_file("lib/c.lang", "pub let c: u64 = 2\n");
+        let mut compiler = Compiler::new();
+        compiler.set_loader(loader);
+        compiler
+    }
+
+    #[test]
+    fn parse_paths() {
+        let mut compiler = compiler();
+        let program = compiler
+            .parse_paths(&["a.lang".into(), "b.lang".into()])
+            .unwrap();
+        let file = program.as_file().unwrap();
+        assert_eq!(file.expressions.len(), 3);
+        assert_eq!(compiler.source_map().filepath(&file.location), "a.lang");
+
+        assert!(compiler.parse_paths(&[]).unwrap_err().is_no_input_files());
+        assert!(matches!(
+            compiler.parse_paths(&["missing.lang".into()]),
+            Err(CompileError::UnableToReadInput { .. })
+        ));
+    }
+
+    #[test]
+    fn compile_program() {
+        let mut compiler = compiler();
+        let program = compiler
+            .parse_paths(&["a.lang".into(), "b.lang".into()])
+            .unwrap();
+        let output = String::from_utf8(compiler.compile_program(&program).unwrap()).unwrap();
+        assert!(output.contains("#line 1 \"a.lang\""));
+        assert!(output.contains("#line 2 \"b.lang\""));
+        assert!(!output.contains("#define LANGITE_BOUNDS_CHECKS"));
+    }
+
+    #[test]
+    fn loader() {
+        let source = "#import \"lib/c.lang\"\nvar d: u64 <- c\n";
+        let mut compiler = compiler();
+        assert!(compiler.loader().load("lib/c.lang").is_ok());
+        compiler.compile_source("d.lang", source).unwrap();
+
+        // the loader is the only place imports can come from
+        let mut compiler = Compiler::new();
+        compiler.set_loader(MemoryLoader::new());
+        let error = compiler.compile_source("d.lang", source).unwrap_err();
+        assert!(matches!(error, CompileError::Parsing(_)));
+    }
+
+    #[test]
+    fn bounds_checks() {
+        let mut compiler = compiler();
+        compiler.set_bounds_checks(true);
+        let output = compiler.compile_path("a.lang").unwrap();
+        assert!(output.starts_with(b"#define LANGITE_BOUNDS_CHECKS\n"));
+    }
+}
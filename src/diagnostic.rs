@@ -0,0 +1,171 @@
+use derive_more::Display;
+use enum_as_inner::EnumAsInner;
+
+use crate::{SourceMap, SourceSpan};
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, EnumAsInner)]
+pub enum Severity {
+    #[display(fmt = "error")]
+    Error,
+    #[display(fmt = "warning")]
+    Warning,
+    #[display(fmt = "note")]
+    Note,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub location: SourceSpan,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: SourceSpan,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, location: SourceSpan, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            location,
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    pub fn error(location: SourceSpan, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, location, message)
+    }
+
+    pub fn with_label(mut self, location: SourceSpan, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            location,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Writes the diagnostic with the source line of every span underlined, the source
+    /// is taken from the files in `source_map`.
+    pub fn render(
+        &self,
+        source_map: &SourceMap,
+        stream: &mut dyn std::io::Write,
+    ) -> Result<(), std::io::Error> {
+        let gutter_width = std::iter::once(&self.location)
+            .chain(self.labels.iter().map(|label| &label.location))
+            .map(|location| source_map.start_location(location).line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(gutter_width);
+
+        writeln!(stream, "{}: {}", self.severity, self.message)?;
+        writeln!(stream, "{gutter}--> {}", source_map.display(&self.location))?;
+        render_snippet(source_map, &self.location, '^', "", gutter_width, stream)?;
+        for label in &self.labels {
+            if label.location.file != self.location.file {
+                writeln!(
+                    stream,
+                    "{gutter}::: {}",
+                    source_map.display(&label.location)
+                )?;
+            }
+            render_snippet(
+                source_map,
+                &label.location,
+                '-',
+                &label.message,
+                gutter_width,
+                stream,
+            )?;
+        }
+        for note in &self.notes {
+            writeln!(stream, "{gutter} = note: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+fn render_snippet(
+    source_map: &SourceMap,
+    location: &SourceSpan,
+    marker: char,
+    message: &str,
+    gutter_width: usize,
+    stream: &mut dyn std::io::Write,
+) -> Result<(), std::io::Error> {
+    let file = source_map.get(location.file);
+    let start = file.location(location.start);
+    let end = file.location(location.end);
+    let line = file.source.lines().nth(start.line - 1).unwrap_or_default();
+    let gutter = " ".repeat(gutter_width);
+
+    // keep tabs in the padding so the markers line up with the line above them
+    let padding = line
+        .chars()
+        .take(start.column - 1)
+        .map(|chr| if chr == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    let line_length = line.chars().count();
+    let marker_count = if end.line == start.line {
+        end.column.saturating_sub(start.column)
+    } else {
+        line_length.saturating_sub(start.column - 1)
+    }
+    .max(1);
+    let markers = marker.to_string().repeat(marker_count);
+
+    writeln!(stream, "{gutter} |")?;
+    writeln!(stream, "{:>gutter_width$} | {line}", start.line)?;
+    if message.is_empty() {
+        writeln!(stream, "{gutter} | {padding}{markers}")?;
+    } else {
+        writeln!(stream, "{gutter} | {padding}{markers} {message}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Diagnostic, FileId, SourceMap, SourceSpan};
+
+    fn span(file: FileId, start: usize, end: usize) -> SourceSpan {
+        SourceSpan { file, start, end }
+    }
+
+    #[test]
+    fn render_with_label() {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add("test.lang", "let x = 1\nlet x = 2\n");
+        let diagnostic = Diagnostic::error(span(file, 14, 15), "Redeclaration of 'x'")
+            .with_label(span(file, 4, 5), "original declaration")
+            .with_note("names can only be declared once per scope");
+        let mut output = vec![];
+        diagnostic.render(&source_map, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\
+error: Redeclaration of 'x'
+ --> test.lang:2:5
+  |
+2 | let x = 2
+  |     ^
+  |
+1 | let x = 1
+  |     - original declaration
+  = note: names can only be declared once per scope
+"
+        );
+    }
+}
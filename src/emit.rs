@@ -1,11 +1,11 @@
 use std::rc::Rc;
 
 use crate::{
-    Ast, AstAssignDirection, AstParameter, AstProcedure, AstProcedureBody, BinaryOperator,
-    CallingConvention, SourceSpan, Type, UnaryOperator,
+    is_constant, Ast, AstAssignDirection, AstParameter, AstProcedure, AstProcedureBody,
+    BinaryOperator, CallingConvention, SourceMap, SourceSpan, Type, UnaryOperator,
 };
 
-const PREFIX: &'static str = "_";
+const PREFIX: &str = "_";
 
 fn calling_convention_c_name(convention: &CallingConvention) -> &'static str {
     match convention {
@@ -17,12 +17,14 @@ fn calling_convention_c_name(convention: &CallingConvention) -> &'static str {
 
 fn emit_line_info(
     location: &SourceSpan,
+    source_map: &SourceMap,
     stream: &mut dyn std::io::Write,
 ) -> Result<(), std::io::Error> {
     writeln!(
         stream,
         "#line {} {:?}",
-        location.start.line, location.filepath
+        source_map.start_location(location).line,
+        source_map.filepath(location)
     )
 }
 
@@ -31,7 +33,7 @@ fn emit_type(
     name: Option<String>,
     stream: &mut dyn std::io::Write,
 ) -> Result<(), std::io::Error> {
-    Ok(match typ {
+    match typ {
         Type::Type => {
             write!(stream, "type")?;
             if let Some(name) = name {
@@ -69,7 +71,7 @@ fn emit_type(
                 write!(stream, "({calling_convention_name}*)")?;
             }
             write!(stream, "(")?;
-            if parameter_types.len() == 0 {
+            if parameter_types.is_empty() {
                 write!(stream, "void")?;
             } else {
                 for (i, parameter_type) in parameter_types.iter().enumerate() {
@@ -82,7 +84,39 @@ fn emit_type(
             write!(stream, ")")?;
         }
         Type::Pointer { pointed_to } => emit_type_ptr(pointed_to, name, stream)?,
-    })
+        Type::Struct { .. } | Type::Array { .. } | Type::Slice { .. } => {
+            write!(stream, "{}", compound_c_name(typ))?;
+            if let Some(name) = name {
+                write!(stream, " {name}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Structs, arrays and slices are all emitted as C structs, arrays so that they are copied
+/// when passed and returned by value.
+fn compound_c_name(typ: &Type) -> String {
+    let kind = match typ {
+        Type::Array { .. } => "array",
+        Type::Slice { .. } => "slice",
+        _ => "struct",
+    };
+    format!("{PREFIX}{kind}{}", typ as *const Type as usize)
+}
+
+/// The C expressions for the elements and the length of an array or slice, or of one behind
+/// a pointer, given the id of the pointer to its value.
+fn elements_and_length(typ: &Type, id: usize) -> (String, String) {
+    let (typ, value) = match typ.as_pointer() {
+        Some(pointed_to) => (&**pointed_to, format!("(*{PREFIX}{id})")),
+        None => (typ, format!("{PREFIX}{id}")),
+    };
+    match typ {
+        Type::Array { length, .. } => (format!("{value}->elements"), length.to_string()),
+        Type::Slice { .. } => (format!("{value}->elements"), format!("{value}->length")),
+        _ => unreachable!("only arrays and slices have elements"),
+    }
 }
 
 fn emit_type_ptr(
@@ -99,57 +133,107 @@ fn emit_type_ptr(
 
 pub fn emit(
     ast: &Ast,
+    type_cache: &[Rc<Type>],
+    source_map: &SourceMap,
     next_id: &mut usize,
     stream: &mut dyn std::io::Write,
 ) -> Result<usize, std::io::Error> {
     Ok(match ast {
         Ast::File(file) => {
-            write!(stream, "typedef unsigned long long type;\n")?;
-            write!(stream, "typedef signed char s8;\n")?;
-            write!(
+            writeln!(stream, "typedef unsigned long long type;")?;
+            writeln!(stream, "typedef signed char s8;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(s8) == 1, \"Expected s8 to be 1 byte\");\n"
+                "_Static_assert(sizeof(s8) == 1, \"Expected s8 to be 1 byte\");"
             )?;
-            write!(stream, "typedef signed short s16;\n")?;
-            write!(
+            writeln!(stream, "typedef signed short s16;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(s16) == 2, \"Expected s16 to be 2 bytes\");\n"
+                "_Static_assert(sizeof(s16) == 2, \"Expected s16 to be 2 bytes\");"
             )?;
-            write!(stream, "typedef signed int s32;\n")?;
-            write!(
+            writeln!(stream, "typedef signed int s32;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(s32) == 4, \"Expected s32 to be 4 bytes\");\n"
+                "_Static_assert(sizeof(s32) == 4, \"Expected s32 to be 4 bytes\");"
             )?;
-            write!(stream, "typedef signed long long s64;\n")?;
-            write!(
+            writeln!(stream, "typedef signed long long s64;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(s64) == 8, \"Expected s64 to be 8 bytes\");\n"
+                "_Static_assert(sizeof(s64) == 8, \"Expected s64 to be 8 bytes\");"
             )?;
-            write!(stream, "typedef unsigned char u8;\n")?;
-            write!(
+            writeln!(stream, "typedef unsigned char u8;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(u8) == 1, \"Expected u8 to be 1 byte\");\n"
+                "_Static_assert(sizeof(u8) == 1, \"Expected u8 to be 1 byte\");"
             )?;
-            write!(stream, "typedef unsigned short u16;\n")?;
-            write!(
+            writeln!(stream, "typedef unsigned short u16;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(u16) == 2, \"Expected u16 to be 2 bytes\");\n"
+                "_Static_assert(sizeof(u16) == 2, \"Expected u16 to be 2 bytes\");"
             )?;
-            write!(stream, "typedef unsigned int u32;\n")?;
-            write!(
+            writeln!(stream, "typedef unsigned int u32;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(u32) == 4, \"Expected u32 to be 4 bytes\");\n"
+                "_Static_assert(sizeof(u32) == 4, \"Expected u32 to be 4 bytes\");"
             )?;
-            write!(stream, "typedef unsigned long long u64;\n")?;
-            write!(
+            writeln!(stream, "typedef unsigned long long u64;")?;
+            writeln!(
                 stream,
-                "_Static_assert(sizeof(u64) == 8, \"Expected u64 to be 8 bytes\");\n"
+                "_Static_assert(sizeof(u64) == 8, \"Expected u64 to be 8 bytes\");"
             )?;
-            write!(stream, "\n")?;
-            write!(stream, "typedef struct {{\n")?;
-            write!(stream, "char buffer[0];\n")?;
-            write!(stream, "}} Void;\n")?;
-            write!(stream, "\n")?;
+            writeln!(stream)?;
+            writeln!(stream, "typedef struct {{")?;
+            writeln!(stream, "char buffer[0];")?;
+            writeln!(stream, "}} Void;")?;
+            writeln!(stream)?;
+            writeln!(stream, "#ifdef LANGITE_BOUNDS_CHECKS")?;
+            writeln!(
+                stream,
+                "#define {PREFIX}bounds_check(condition) do {{ if (!(condition)) __builtin_trap(); }} while (0)"
+            )?;
+            writeln!(stream, "#else")?;
+            writeln!(stream, "#define {PREFIX}bounds_check(condition)")?;
+            writeln!(stream, "#endif")?;
+            writeln!(stream)?;
+            {
+                let compounds = type_cache
+                    .iter()
+                    .filter(|typ| {
+                        typ.as_struct().is_some()
+                            || typ.as_array().is_some()
+                            || typ.as_slice().is_some()
+                    })
+                    .collect::<Vec<_>>();
+                for typ in &compounds {
+                    let name = compound_c_name(typ);
+                    writeln!(stream, "typedef struct {name} {name};")?;
+                }
+                // the types of the fields and elements are always added to the cache first
+                for typ in &compounds {
+                    writeln!(stream, "struct {} {{", compound_c_name(typ))?;
+                    if let Some((length, element_type)) = typ.as_array() {
+                        emit_type(element_type, format!("elements[{length}]").into(), stream)?;
+                        writeln!(stream, ";")?;
+                    } else if let Some(element_type) = typ.as_slice() {
+                        emit_type_ptr(element_type, "elements".to_string().into(), stream)?;
+                        writeln!(stream, ";")?;
+                        writeln!(stream, "u64 length;")?;
+                    } else {
+                        let fields = typ.as_struct().unwrap();
+                        if fields.is_empty() {
+                            writeln!(stream, "char buffer[0];")?;
+                        }
+                        for (name, field_type) in fields {
+                            emit_type(field_type, format!("{PREFIX}{name}").into(), stream)?;
+                            writeln!(stream, ";")?;
+                        }
+                    }
+                    writeln!(stream, "}};")?;
+                }
+                if !compounds.is_empty() {
+                    writeln!(stream)?;
+                }
+            }
             {
                 fn get_all_procedures(
                     ast: &Ast,
@@ -165,6 +249,13 @@ pub fn emit(
                                     get_all_procedures(expression, procedures, walked);
                                 }
                             }
+                            Ast::Import(import) => {
+                                get_all_procedures(
+                                    &Ast::File(import.file.clone()),
+                                    procedures,
+                                    walked,
+                                );
+                            }
                             Ast::Procedure(procedure) => {
                                 procedures.push(procedure.clone());
                                 for parameter in &procedure.parameters {
@@ -217,7 +308,20 @@ pub fn emit(
                                     walked,
                                 );
                             }
+                            Ast::MemberAccess(member_access) => {
+                                match &*member_access.resolved_declaration.borrow() {
+                                    Some(declaration) => {
+                                        get_all_procedures(declaration, procedures, walked)
+                                    }
+                                    None => get_all_procedures(
+                                        &member_access.operand,
+                                        procedures,
+                                        walked,
+                                    ),
+                                }
+                            }
                             Ast::Integer(_) => (),
+                            Ast::String(_) => (),
                             Ast::Call(call) => {
                                 get_all_procedures(&call.operand, procedures, walked);
                                 for argument in &call.arguments {
@@ -262,6 +366,38 @@ pub fn emit(
                                 }
                             },
                             Ast::Builtin(_) => (),
+                            Ast::StructType(struct_type) => {
+                                for field in &struct_type.fields {
+                                    get_all_procedures(&field.value, procedures, walked);
+                                }
+                            }
+                            Ast::StructLiteral(literal) => {
+                                get_all_procedures(&literal.typ, procedures, walked);
+                                for field in &literal.fields {
+                                    get_all_procedures(&field.value, procedures, walked);
+                                }
+                            }
+                            Ast::ArrayType(array_type) => {
+                                get_all_procedures(&array_type.length, procedures, walked);
+                                get_all_procedures(&array_type.element_type, procedures, walked);
+                            }
+                            Ast::ArrayLiteral(literal) => {
+                                for element in &literal.elements {
+                                    get_all_procedures(element, procedures, walked);
+                                }
+                            }
+                            Ast::Index(index) => {
+                                get_all_procedures(&index.operand, procedures, walked);
+                                get_all_procedures(&index.index, procedures, walked);
+                            }
+                            Ast::SliceType(slice_type) => {
+                                get_all_procedures(&slice_type.element_type, procedures, walked);
+                            }
+                            Ast::Slice(slice) => {
+                                get_all_procedures(&slice.operand, procedures, walked);
+                                get_all_procedures(&slice.start, procedures, walked);
+                                get_all_procedures(&slice.end, procedures, walked);
+                            }
                         }
                     }
                 }
@@ -283,7 +419,7 @@ pub fn emit(
                         calling_convention_c_name(calling_convention)
                     )?;
                     write!(stream, "(")?;
-                    if parameters.len() == 0 {
+                    if parameters.is_empty() {
                         write!(stream, "void")?;
                     } else {
                         for (i, parameter) in parameters.iter().enumerate() {
@@ -309,7 +445,7 @@ pub fn emit(
                             let typ = procedure.resolved_type.borrow();
                             let typ = typ.as_ref().unwrap();
                             let return_type = typ.as_procedure().unwrap().1;
-                            emit_line_info(&procedure.location, stream)?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
                             write!(stream, "extern ")?;
                             emit_function_decl(
                                 &procedure.parameters,
@@ -318,8 +454,8 @@ pub fn emit(
                                 name,
                                 stream,
                             )?;
-                            write!(stream, ";\n")?;
-                            emit_line_info(&procedure.location, stream)?;
+                            writeln!(stream, ";")?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
                             write!(stream, "static ")?;
                             emit_type(
                                 typ,
@@ -327,7 +463,7 @@ pub fn emit(
                                     .into(),
                                 stream,
                             )?;
-                            write!(stream, " = &{};\n", name)?;
+                            writeln!(stream, " = &{};", name)?;
                         }
                         AstProcedureBody::Scope(_) => {
                             let typ = procedure.resolved_type.borrow();
@@ -335,7 +471,7 @@ pub fn emit(
                             let return_type = typ.as_procedure().unwrap().1;
                             let name =
                                 format!("_{}_{}", Rc::as_ptr(procedure) as usize, procedure.name);
-                            emit_line_info(&procedure.location, stream)?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
                             write!(stream, "static ")?;
                             emit_function_decl(
                                 &procedure.parameters,
@@ -344,16 +480,16 @@ pub fn emit(
                                 &format!("_impl{name}"),
                                 stream,
                             )?;
-                            write!(stream, ";\n")?;
-                            emit_line_info(&procedure.location, stream)?;
+                            writeln!(stream, ";")?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
                             write!(stream, "static ")?;
                             emit_type(typ, name.clone().into(), stream)?;
-                            write!(stream, " = &_impl{name};\n")?;
+                            writeln!(stream, " = &_impl{name};")?;
                         }
                     }
                 }
 
-                write!(stream, "\n")?;
+                writeln!(stream)?;
 
                 // implementation
                 for procedure in &procedures {
@@ -363,7 +499,7 @@ pub fn emit(
                             let mut next_id = *next_id;
                             let typ = procedure.resolved_type.borrow();
                             let return_type = typ.as_ref().unwrap().as_procedure().unwrap().1;
-                            emit_line_info(&procedure.location, stream)?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
                             write!(stream, "static ")?;
                             emit_function_decl(
                                 &procedure.parameters,
@@ -376,49 +512,72 @@ pub fn emit(
                                 ),
                                 stream,
                             )?;
-                            write!(stream, " {{\n")?;
-                            emit(&Ast::Scope(scope.clone()), &mut next_id, stream)?;
+                            writeln!(stream, " {{")?;
+                            emit(
+                                &Ast::Scope(scope.clone()),
+                                type_cache,
+                                source_map,
+                                &mut next_id,
+                                stream,
+                            )?;
                             if return_type.is_void() {
-                                emit_line_info(&procedure.location, stream)?;
-                                write!(stream, "return (Void){{}};\n")?;
+                                emit_line_info(&procedure.location, source_map, stream)?;
+                                writeln!(stream, "return (Void){{}};")?;
                             }
-                            emit_line_info(&procedure.location, stream)?;
-                            write!(stream, "}}\n")?;
+                            emit_line_info(&procedure.location, source_map, stream)?;
+                            writeln!(stream, "}}")?;
                         }
                     }
                 }
             }
             {
                 let mut next_id = *next_id;
-                write!(stream, "\n")?;
-                emit_line_info(&file.location, stream)?;
-                write!(stream, "int main(void) {{\n")?;
+                writeln!(stream)?;
+                emit_line_info(&file.location, source_map, stream)?;
+                writeln!(stream, "int main(void) {{")?;
                 for expression in &file.expressions {
-                    emit(expression, &mut next_id, stream)?;
+                    emit(expression, type_cache, source_map, &mut next_id, stream)?;
                 }
                 let end_location = SourceSpan {
-                    filepath: file.location.filepath.clone(),
-                    start: file.location.end.clone(),
-                    end: file.location.end.clone(),
+                    file: file.location.file,
+                    start: file.location.end,
+                    end: file.location.end,
                 };
-                emit_line_info(&end_location, stream)?;
-                write!(stream, "return 0;\n")?;
-                emit_line_info(&end_location, stream)?;
-                write!(stream, "}}\n")?;
+                emit_line_info(&end_location, source_map, stream)?;
+                writeln!(stream, "return 0;")?;
+                emit_line_info(&end_location, source_map, stream)?;
+                writeln!(stream, "}}")?;
             }
             usize::MAX
         }
+        Ast::Import(import) => {
+            if import.is_first {
+                for expression in &import.file.expressions {
+                    emit(expression, type_cache, source_map, next_id, stream)?;
+                }
+            }
+            let id = *next_id;
+            *next_id += 1;
+            emit_line_info(&import.location, source_map, stream)?;
+            emit_type_ptr(
+                import.resolved_type.borrow().as_ref().unwrap(),
+                format!("{PREFIX}{id}").into(),
+                stream,
+            )?;
+            writeln!(stream, " = &(Void){{}};")?;
+            id
+        }
         Ast::Procedure(procedure) => match &procedure.body {
             AstProcedureBody::ExternName(_) => {
                 let typ = procedure.resolved_type.borrow();
                 let typ = typ.as_ref().unwrap();
                 let id = *next_id;
                 *next_id += 1;
-                emit_line_info(&procedure.location, stream)?;
+                emit_line_info(&procedure.location, source_map, stream)?;
                 emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
-                write!(
+                writeln!(
                     stream,
-                    " = &_{}_{};\n",
+                    " = &_{}_{};",
                     Rc::as_ptr(procedure) as usize,
                     procedure.name
                 )?;
@@ -429,11 +588,11 @@ pub fn emit(
                 let typ = typ.as_ref().unwrap();
                 let id = *next_id;
                 *next_id += 1;
-                emit_line_info(&procedure.location, stream)?;
+                emit_line_info(&procedure.location, source_map, stream)?;
                 emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
-                write!(
+                writeln!(
                     stream,
-                    " = &_{}_{};\n",
+                    " = &_{}_{};",
                     Rc::as_ptr(procedure) as usize,
                     procedure.name
                 )?;
@@ -444,62 +603,66 @@ pub fn emit(
         Ast::Parameter(_) => unreachable!(), // this is handled elsewhere
         Ast::Scope(scope) => {
             for expression in &scope.expressions {
-                emit(expression, next_id, stream)?;
+                emit(expression, type_cache, source_map, next_id, stream)?;
             }
             let id = *next_id;
             *next_id += 1;
             let typ = scope.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            emit_line_info(&scope.location, stream)?;
+            emit_line_info(&scope.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             assert!(typ.is_void());
-            write!(stream, " = &(Void){{}};\n")?;
+            writeln!(stream, " = &(Void){{}};")?;
             id
         }
         Ast::LetDeclaration(declaration) => {
             let typ = declaration.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            let value = emit(&declaration.value, next_id, stream)?;
+            let value = emit(&declaration.value, type_cache, source_map, next_id, stream)?;
             let id = *next_id;
             *next_id += 1;
             let name = format!("_{}_{}", Rc::as_ptr(declaration) as usize, declaration.name);
-            emit_line_info(&declaration.location, stream)?;
-            emit_type(&typ, name.clone().into(), stream)?;
-            write!(stream, " = *{PREFIX}{value};\n")?;
-            emit_line_info(&declaration.location, stream)?;
-            emit_type_ptr(&typ, format!("{PREFIX}{id}").into(), stream)?;
-            write!(stream, " = &{name};\n")?;
+            emit_line_info(&declaration.location, source_map, stream)?;
+            emit_type(typ, name.clone().into(), stream)?;
+            writeln!(stream, " = *{PREFIX}{value};")?;
+            emit_line_info(&declaration.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            writeln!(stream, " = &{name};")?;
             id
         }
         Ast::VarDeclaration(declaration) => {
             let typ = declaration.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            let value = emit(&declaration.value, next_id, stream)?;
+            let value = emit(&declaration.value, type_cache, source_map, next_id, stream)?;
             let id = *next_id;
             *next_id += 1;
             let name = format!("_{}_{}", Rc::as_ptr(declaration) as usize, declaration.name);
-            emit_line_info(&declaration.location, stream)?;
-            emit_type(&typ, name.clone().into(), stream)?;
-            write!(stream, " = *{PREFIX}{value};\n")?;
-            emit_line_info(&declaration.location, stream)?;
-            emit_type_ptr(&typ, format!("{PREFIX}{id}").into(), stream)?;
-            write!(stream, " = &{name};\n")?;
+            emit_line_info(&declaration.location, source_map, stream)?;
+            emit_type(typ, name.clone().into(), stream)?;
+            writeln!(stream, " = *{PREFIX}{value};")?;
+            emit_line_info(&declaration.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            writeln!(stream, " = &{name};")?;
             id
         }
         Ast::Name(name) => {
             let declaration = name.resolved_declaration.borrow();
             let declaration = declaration.as_ref().unwrap();
             match declaration {
-                Ast::Builtin(_) => emit(declaration, next_id, stream)?,
+                Ast::Builtin(_) => emit(declaration, type_cache, source_map, next_id, stream)?,
+                // constants are visible inside procedures, where the variable holding them is not
+                Ast::LetDeclaration(declaration) if is_constant(&declaration.value) => {
+                    emit(&declaration.value, type_cache, source_map, next_id, stream)?
+                }
                 _ => {
                     let typ = declaration.get_type().unwrap();
                     let id = *next_id;
                     *next_id += 1;
-                    emit_line_info(&name.location, stream)?;
+                    emit_line_info(&name.location, source_map, stream)?;
                     emit_type_ptr(&typ, format!("{PREFIX}{id}").into(), stream)?;
-                    write!(
+                    writeln!(
                         stream,
-                        " = &_{}_{};\n",
+                        " = &_{}_{};",
                         declaration.get_ptr() as usize,
                         name.name
                     )?;
@@ -507,28 +670,103 @@ pub fn emit(
                 }
             }
         }
+        Ast::MemberAccess(member_access) => match &*member_access.resolved_declaration.borrow() {
+            Some(declaration @ Ast::Builtin(_)) => {
+                emit(declaration, type_cache, source_map, next_id, stream)?
+            }
+            Some(Ast::LetDeclaration(declaration)) if is_constant(&declaration.value) => {
+                emit(&declaration.value, type_cache, source_map, next_id, stream)?
+            }
+            Some(declaration) => {
+                let typ = declaration.get_type().unwrap();
+                let id = *next_id;
+                *next_id += 1;
+                emit_line_info(&member_access.location, source_map, stream)?;
+                emit_type_ptr(&typ, format!("{PREFIX}{id}").into(), stream)?;
+                writeln!(
+                    stream,
+                    " = &_{}_{};",
+                    declaration.get_ptr() as usize,
+                    member_access.name
+                )?;
+                id
+            }
+            None => {
+                let operand = emit(
+                    &member_access.operand,
+                    type_cache,
+                    source_map,
+                    next_id,
+                    stream,
+                )?;
+                let typ = member_access.resolved_type.borrow();
+                let typ = typ.as_ref().unwrap();
+                let id = *next_id;
+                *next_id += 1;
+                emit_line_info(&member_access.location, source_map, stream)?;
+                emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+                let name = &member_access.name;
+                let operand_type = member_access.operand.get_type().unwrap();
+                if operand_type
+                    .as_pointer()
+                    .unwrap_or(&operand_type)
+                    .as_struct()
+                    .is_none()
+                {
+                    let (_, length) = elements_and_length(&operand_type, operand);
+                    write!(stream, " = &(")?;
+                    emit_type(typ, None, stream)?;
+                    writeln!(stream, "){{{length}}};")?;
+                } else if operand_type.as_pointer().is_some() {
+                    writeln!(stream, " = &(*{PREFIX}{operand})->{PREFIX}{name};")?;
+                } else {
+                    writeln!(stream, " = &{PREFIX}{operand}->{PREFIX}{name};")?;
+                }
+                id
+            }
+        },
         Ast::Integer(integer) => {
             let id = *next_id;
             *next_id += 1;
             let typ = integer.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            emit_line_info(&integer.location, stream)?;
+            emit_line_info(&integer.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             write!(stream, " = &(")?;
             emit_type(typ, None, stream)?;
-            write!(stream, "){{{}}};\n", integer.value)?;
+            writeln!(stream, "){{{}}};", integer.value)?;
+            id
+        }
+        Ast::String(string) => {
+            let id = *next_id;
+            *next_id += 1;
+            let typ = string.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&string.location, source_map, stream)?;
+            // the terminator is not part of the length, it is only there for C functions, and
+            // the data is static so slices of it outlive the procedure, which also means that
+            // writing through one changes what later evaluations of the literal see
+            write!(stream, "static u8 {PREFIX}{id}_data[] = {{")?;
+            for byte in string.value.bytes() {
+                write!(stream, "{byte}, ")?;
+            }
+            writeln!(stream, "0}};")?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            writeln!(stream, "){{{PREFIX}{id}_data, {}}};", string.value.len())?;
             id
         }
         Ast::Call(call) => {
-            let operand = emit(&call.operand, next_id, stream)?;
+            let operand = emit(&call.operand, type_cache, source_map, next_id, stream)?;
             let arguments = call
                 .arguments
                 .iter()
-                .map(|argument| emit(argument, next_id, stream))
+                .map(|argument| emit(argument, type_cache, source_map, next_id, stream))
                 .collect::<Result<Vec<_>, _>>()?;
             let return_id = *next_id;
             *next_id += 1;
-            emit_line_info(&call.location, stream)?;
+            emit_line_info(&call.location, source_map, stream)?;
             emit_type(
                 call.resolved_type.borrow().as_ref().unwrap(),
                 format!("{PREFIX}{return_id}").into(),
@@ -541,84 +779,84 @@ pub fn emit(
                 }
                 write!(stream, "*{PREFIX}{argument}")?;
             }
-            write!(stream, ");\n")?;
+            writeln!(stream, ");")?;
             let id = *next_id;
             *next_id += 1;
-            emit_line_info(&call.location, stream)?;
+            emit_line_info(&call.location, source_map, stream)?;
             emit_type_ptr(
                 call.resolved_type.borrow().as_ref().unwrap(),
                 format!("{PREFIX}{id}").into(),
                 stream,
             )?;
-            write!(stream, " = &{PREFIX}{return_id};\n")?;
+            writeln!(stream, " = &{PREFIX}{return_id};")?;
             id
         }
         Ast::Return(returnn) => {
             if let Some(value) = &returnn.value {
-                let value_id = emit(value, next_id, stream)?;
-                emit_line_info(&returnn.location, stream)?;
-                write!(stream, "return *{PREFIX}{value_id};\n")?;
+                let value_id = emit(value, type_cache, source_map, next_id, stream)?;
+                emit_line_info(&returnn.location, source_map, stream)?;
+                writeln!(stream, "return *{PREFIX}{value_id};")?;
             } else {
-                emit_line_info(&returnn.location, stream)?;
-                write!(stream, "return (Void){{}};\n")?;
+                emit_line_info(&returnn.location, source_map, stream)?;
+                writeln!(stream, "return (Void){{}};")?;
             }
             let id = *next_id;
             *next_id += 1;
             let typ = returnn.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            emit_line_info(&returnn.location, stream)?;
+            emit_line_info(&returnn.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             assert!(typ.is_void());
-            write!(stream, " = &(Void){{}};\n")?;
+            writeln!(stream, " = &(Void){{}};")?;
             id
         }
         Ast::Unary(unary) => {
             let typ = unary.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            let operand = emit(&unary.operand, next_id, stream)?;
+            let operand = emit(&unary.operand, type_cache, source_map, next_id, stream)?;
             let id = *next_id;
             *next_id += 1;
-            emit_line_info(&unary.location, stream)?;
+            emit_line_info(&unary.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             write!(stream, " = ")?;
             match &unary.operator {
                 UnaryOperator::Identity => {
                     write!(stream, "&(")?;
                     emit_type(typ, None, stream)?;
-                    write!(stream, "){{*{PREFIX}{operand}}};\n")?;
+                    writeln!(stream, "){{*{PREFIX}{operand}}};")?;
                 }
                 UnaryOperator::Negation => {
                     write!(stream, "&(")?;
                     emit_type(typ, None, stream)?;
                     assert!(unary.operand.get_type().unwrap().as_integer().is_some());
-                    write!(stream, "){{-*{PREFIX}{operand}}};\n")?;
+                    writeln!(stream, "){{-*{PREFIX}{operand}}};")?;
                 }
                 UnaryOperator::LogicalNot => {
                     write!(stream, "&(")?;
                     emit_type(typ, None, stream)?;
                     assert!(unary.operand.get_type().unwrap().is_bool());
-                    write!(stream, "){{!*{PREFIX}{operand}}};\n")?;
+                    writeln!(stream, "){{!*{PREFIX}{operand}}};")?;
                 }
                 UnaryOperator::PointerType => todo!(),
                 UnaryOperator::AddressOf => {
                     write!(stream, "&(")?;
                     emit_type(typ, None, stream)?;
-                    write!(stream, "){{{PREFIX}{operand}}};\n")?;
+                    writeln!(stream, "){{{PREFIX}{operand}}};")?;
                 }
                 UnaryOperator::Dereference => {
-                    write!(stream, "*{PREFIX}{operand};\n")?;
+                    writeln!(stream, "*{PREFIX}{operand};")?;
                 }
             }
             id
         }
         Ast::Binary(binary) => {
-            let left = emit(&binary.left, next_id, stream)?;
-            let right = emit(&binary.right, next_id, stream)?;
+            let left = emit(&binary.left, type_cache, source_map, next_id, stream)?;
+            let right = emit(&binary.right, type_cache, source_map, next_id, stream)?;
             let id = *next_id;
             *next_id += 1;
             let typ = binary.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            emit_line_info(&binary.location, stream)?;
+            emit_line_info(&binary.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             write!(stream, " = &(")?;
             emit_type(typ, None, stream)?;
@@ -642,41 +880,45 @@ pub fn emit(
                     write!(stream, "*{PREFIX}{left} >= *{PREFIX}{right}")?
                 }
             }
-            write!(stream, "}};\n")?;
+            writeln!(stream, "}};")?;
             id
         }
         Ast::If(iff) => {
             let typ = iff.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            let condition = emit(&iff.condition, next_id, stream)?;
+            let condition = emit(&iff.condition, type_cache, source_map, next_id, stream)?;
             let else_id = *next_id;
             *next_id += 1;
             let id = *next_id;
             *next_id += 1;
-            emit_line_info(&iff.location, stream)?;
+            emit_line_info(&iff.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             assert!(typ.is_void());
-            write!(stream, " = &(Void){{}};\n")?;
-            emit_line_info(&iff.condition.get_location(), stream)?;
-            write!(
+            writeln!(stream, " = &(Void){{}};")?;
+            emit_line_info(&iff.condition.get_location(), source_map, stream)?;
+            writeln!(stream, "if (!*{PREFIX}{condition}) goto {PREFIX}{else_id};")?;
+            let then_expression = emit(
+                &iff.then_expression,
+                type_cache,
+                source_map,
+                next_id,
                 stream,
-                "if (!*{PREFIX}{condition}) goto {PREFIX}{else_id};\n"
             )?;
-            let then_expression = emit(&iff.then_expression, next_id, stream)?;
-            emit_line_info(&iff.then_expression.get_location(), stream)?;
-            write!(stream, "{PREFIX}{id} = {PREFIX}{then_expression};\n")?;
+            emit_line_info(&iff.then_expression.get_location(), source_map, stream)?;
+            writeln!(stream, "{PREFIX}{id} = {PREFIX}{then_expression};")?;
             let end_id = *next_id;
             *next_id += 1;
-            emit_line_info(&iff.then_expression.get_location(), stream)?;
-            write!(stream, "goto {PREFIX}{end_id};\n")?;
-            write!(stream, "{PREFIX}{else_id}:;\n")?;
+            emit_line_info(&iff.then_expression.get_location(), source_map, stream)?;
+            writeln!(stream, "goto {PREFIX}{end_id};")?;
+            writeln!(stream, "{PREFIX}{else_id}:;")?;
             if let Some(else_expression) = &iff.else_expression {
                 let location = else_expression.get_location();
-                let else_expression = emit(else_expression, next_id, stream)?;
-                emit_line_info(&location, stream)?;
-                write!(stream, "{PREFIX}{id} = {PREFIX}{else_expression};\n")?;
+                let else_expression =
+                    emit(else_expression, type_cache, source_map, next_id, stream)?;
+                emit_line_info(&location, source_map, stream)?;
+                writeln!(stream, "{PREFIX}{id} = {PREFIX}{else_expression};")?;
             }
-            write!(stream, "{PREFIX}{end_id}:;\n")?;
+            writeln!(stream, "{PREFIX}{end_id}:;")?;
             id
         }
         Ast::While(whilee) => {
@@ -684,62 +926,83 @@ pub fn emit(
             let typ = typ.as_ref().unwrap();
             let start_id = *next_id;
             *next_id += 1;
-            write!(stream, "{PREFIX}{start_id}:;\n")?;
-            let condition = emit(&whilee.condition, next_id, stream)?;
+            writeln!(stream, "{PREFIX}{start_id}:;")?;
+            let condition = emit(&whilee.condition, type_cache, source_map, next_id, stream)?;
             let id = *next_id;
             *next_id += 1;
-            emit_line_info(&whilee.location, stream)?;
+            emit_line_info(&whilee.location, source_map, stream)?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             assert!(typ.is_void());
-            write!(stream, " = &(Void){{}};\n")?;
+            writeln!(stream, " = &(Void){{}};")?;
             let end_id = *next_id;
             *next_id += 1;
-            write!(
+            writeln!(stream, "if (!*{PREFIX}{condition}) goto {PREFIX}{end_id};")?;
+            let then_expression = emit(
+                &whilee.then_expression,
+                type_cache,
+                source_map,
+                next_id,
                 stream,
-                "if (!*{PREFIX}{condition}) goto {PREFIX}{end_id};\n"
             )?;
-            let then_expression = emit(&whilee.then_expression, next_id, stream)?;
-            emit_line_info(&whilee.then_expression.get_location(), stream)?;
-            write!(stream, "{PREFIX}{id} = {PREFIX}{then_expression};\n")?;
-            emit_line_info(&whilee.then_expression.get_location(), stream)?;
-            write!(stream, "goto {PREFIX}{start_id};\n")?;
-            write!(stream, "{PREFIX}{end_id}:;\n")?;
+            emit_line_info(&whilee.then_expression.get_location(), source_map, stream)?;
+            writeln!(stream, "{PREFIX}{id} = {PREFIX}{then_expression};")?;
+            emit_line_info(&whilee.then_expression.get_location(), source_map, stream)?;
+            writeln!(stream, "goto {PREFIX}{start_id};")?;
+            writeln!(stream, "{PREFIX}{end_id}:;")?;
             id
         }
         Ast::Cast(cast) => {
-            let operand = emit(&cast.operand, next_id, stream)?;
+            let operand = emit(&cast.operand, type_cache, source_map, next_id, stream)?;
             let typ = cast.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            if &cast.operand.get_type().unwrap() == typ {
+            let operand_type = cast.operand.get_type().unwrap();
+            if &operand_type == typ {
                 operand
+            } else if operand_type.as_array().is_some()
+                || operand_type.as_pointer().is_some()
+                || operand_type.as_slice().is_some()
+            {
+                // a pointer to the first element of an array
+                let id = *next_id;
+                *next_id += 1;
+                emit_line_info(&cast.location, source_map, stream)?;
+                emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+                write!(stream, " = &(")?;
+                emit_type(typ, None, stream)?;
+                if operand_type.as_pointer().is_some() {
+                    writeln!(stream, "){{(*{PREFIX}{operand})->elements}};")?;
+                } else {
+                    writeln!(stream, "){{{PREFIX}{operand}->elements}};")?;
+                }
+                id
             } else {
                 assert!(typ.as_integer().is_some());
                 assert!(cast.operand.get_type().unwrap().as_integer().is_some());
                 let id = *next_id;
                 *next_id += 1;
-                emit_line_info(&cast.location, stream)?;
-                emit_type_ptr(&typ, format!("{PREFIX}{id}").into(), stream)?;
+                emit_line_info(&cast.location, source_map, stream)?;
+                emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
                 write!(stream, " = &(")?;
-                emit_type(&typ, None, stream)?;
+                emit_type(typ, None, stream)?;
                 write!(stream, "){{(")?;
-                emit_type(&typ, None, stream)?;
-                write!(stream, ")*{PREFIX}{operand}}};\n")?;
+                emit_type(typ, None, stream)?;
+                writeln!(stream, ")*{PREFIX}{operand}}};")?;
                 id
             }
         }
         Ast::Assign(assign) => match &assign.direction {
             AstAssignDirection::Left => {
-                let operand = emit(&assign.operand, next_id, stream)?;
-                let value = emit(&assign.value, next_id, stream)?;
-                emit_line_info(&assign.location, stream)?;
-                write!(stream, "*{PREFIX}{operand} = *{PREFIX}{value};\n")?;
+                let operand = emit(&assign.operand, type_cache, source_map, next_id, stream)?;
+                let value = emit(&assign.value, type_cache, source_map, next_id, stream)?;
+                emit_line_info(&assign.location, source_map, stream)?;
+                writeln!(stream, "*{PREFIX}{operand} = *{PREFIX}{value};")?;
                 operand
             }
             AstAssignDirection::Right => {
-                let value = emit(&assign.value, next_id, stream)?;
-                let operand = emit(&assign.operand, next_id, stream)?;
-                emit_line_info(&assign.location, stream)?;
-                write!(stream, "*{PREFIX}{operand} = *{PREFIX}{value};\n")?;
+                let value = emit(&assign.value, type_cache, source_map, next_id, stream)?;
+                let operand = emit(&assign.operand, type_cache, source_map, next_id, stream)?;
+                emit_line_info(&assign.location, source_map, stream)?;
+                writeln!(stream, "*{PREFIX}{operand} = *{PREFIX}{value};")?;
                 operand
             }
         },
@@ -748,16 +1011,158 @@ pub fn emit(
             *next_id += 1;
             let typ = builtin.resolved_type.borrow();
             let typ = typ.as_ref().unwrap();
-            emit_line_info(&Ast::Builtin(builtin.clone()).get_location(), stream)?;
+            emit_line_info(
+                &Ast::Builtin(builtin.clone()).get_location(),
+                source_map,
+                stream,
+            )?;
             emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
             write!(stream, " = &(")?;
             emit_type(typ, None, stream)?;
-            write!(
+            writeln!(
                 stream,
-                "){{{}}};\n",
+                "){{{}}};",
                 Rc::as_ptr(builtin.typ.borrow().as_ref().unwrap()) as usize
             )?;
             id
         }
+        Ast::StructType(struct_type) => {
+            let id = *next_id;
+            *next_id += 1;
+            let typ = struct_type.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&struct_type.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            writeln!(
+                stream,
+                "){{{}}};",
+                Rc::as_ptr(struct_type.typ.borrow().as_ref().unwrap()) as usize
+            )?;
+            id
+        }
+        Ast::ArrayType(array_type) => {
+            let id = *next_id;
+            *next_id += 1;
+            let typ = array_type.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&array_type.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            writeln!(
+                stream,
+                "){{{}}};",
+                Rc::as_ptr(array_type.typ.borrow().as_ref().unwrap()) as usize
+            )?;
+            id
+        }
+        Ast::ArrayLiteral(literal) => {
+            let elements = literal
+                .elements
+                .iter()
+                .map(|element| emit(element, type_cache, source_map, next_id, stream))
+                .collect::<Result<Vec<_>, _>>()?;
+            let id = *next_id;
+            *next_id += 1;
+            let typ = literal.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&literal.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            write!(stream, "){{{{")?;
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    write!(stream, ", ")?;
+                }
+                write!(stream, "*{PREFIX}{element}")?;
+            }
+            writeln!(stream, "}}}};")?;
+            id
+        }
+        Ast::Index(index) => {
+            let operand = emit(&index.operand, type_cache, source_map, next_id, stream)?;
+            let index_id = emit(&index.index, type_cache, source_map, next_id, stream)?;
+            let typ = index.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            let id = *next_id;
+            *next_id += 1;
+            let (elements, length) =
+                elements_and_length(&index.operand.get_type().unwrap(), operand);
+            emit_line_info(&index.location, source_map, stream)?;
+            writeln!(
+                stream,
+                "{PREFIX}bounds_check((u64)*{PREFIX}{index_id} < (u64){length});"
+            )?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            writeln!(stream, " = &{elements}[*{PREFIX}{index_id}];")?;
+            id
+        }
+        Ast::SliceType(slice_type) => {
+            let id = *next_id;
+            *next_id += 1;
+            let typ = slice_type.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&slice_type.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            writeln!(
+                stream,
+                "){{{}}};",
+                Rc::as_ptr(slice_type.typ.borrow().as_ref().unwrap()) as usize
+            )?;
+            id
+        }
+        Ast::Slice(slice) => {
+            let operand = emit(&slice.operand, type_cache, source_map, next_id, stream)?;
+            let start = emit(&slice.start, type_cache, source_map, next_id, stream)?;
+            let end = emit(&slice.end, type_cache, source_map, next_id, stream)?;
+            let typ = slice.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            let id = *next_id;
+            *next_id += 1;
+            let (elements, length) =
+                elements_and_length(&slice.operand.get_type().unwrap(), operand);
+            emit_line_info(&slice.location, source_map, stream)?;
+            writeln!(
+                stream,
+                "{PREFIX}bounds_check((u64)*{PREFIX}{start} <= (u64)*{PREFIX}{end} && (u64)*{PREFIX}{end} <= (u64){length});"
+            )?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            writeln!(
+                stream,
+                "){{{elements} + *{PREFIX}{start}, *{PREFIX}{end} - *{PREFIX}{start}}};"
+            )?;
+            id
+        }
+        Ast::StructLiteral(literal) => {
+            let values = literal
+                .fields
+                .iter()
+                .map(|field| emit(&field.value, type_cache, source_map, next_id, stream))
+                .collect::<Result<Vec<_>, _>>()?;
+            let id = *next_id;
+            *next_id += 1;
+            let typ = literal.resolved_type.borrow();
+            let typ = typ.as_ref().unwrap();
+            emit_line_info(&literal.location, source_map, stream)?;
+            emit_type_ptr(typ, format!("{PREFIX}{id}").into(), stream)?;
+            write!(stream, " = &(")?;
+            emit_type(typ, None, stream)?;
+            write!(stream, "){{")?;
+            for (i, (field, value)) in literal.fields.iter().zip(values).enumerate() {
+                if i > 0 {
+                    write!(stream, ", ")?;
+                }
+                write!(stream, ".{PREFIX}{} = *{PREFIX}{value}", field.name)?;
+            }
+            writeln!(stream, "}};")?;
+            id
+        }
     })
 }
@@ -3,8 +3,8 @@ use std::rc::Rc;
 use enum_as_inner::EnumAsInner;
 
 use crate::{
-    get_or_add_type_pointer, get_or_add_type_procedure, Ast, AstProcedure, BinaryOperator, Type,
-    UnaryOperator,
+    add_type_struct, get_or_add_type_array, get_or_add_type_pointer, get_or_add_type_procedure,
+    get_or_add_type_slice, Ast, AstProcedure, BinaryOperator, Type, UnaryOperator,
 };
 
 #[derive(Clone, Debug, EnumAsInner)]
@@ -45,6 +45,23 @@ impl PartialEq for Value {
     }
 }
 
+impl Value {
+    /// The value of any of the integer variants.
+    pub fn to_integer(&self) -> Option<i128> {
+        Some(match *self {
+            Value::S8(value) => value.into(),
+            Value::S16(value) => value.into(),
+            Value::S32(value) => value.into(),
+            Value::S64(value) => value.into(),
+            Value::U8(value) => value.into(),
+            Value::U16(value) => value.into(),
+            Value::U32(value) => value.into(),
+            Value::U64(value) => value.into(),
+            _ => return None,
+        })
+    }
+}
+
 pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
     match ast {
         Ast::File(file) => {
@@ -53,6 +70,7 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             }
             Value::Void.into()
         }
+        Ast::Import(_) => unreachable!("an import is never constant"),
         Ast::Procedure(procedure) => Value::Procedure(procedure.clone()).into(),
         Ast::ProcedureType(procedure_type) => Value::Type({
             let parameter_types = procedure_type
@@ -79,12 +97,20 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             }
             Value::Void.into()
         }
-        Ast::LetDeclaration(_) => todo!(),
+        Ast::LetDeclaration(declaration) => eval(&declaration.value, type_cache),
         Ast::VarDeclaration(_) => todo!(),
         Ast::Name(name) => eval(
             name.resolved_declaration.borrow().as_ref().unwrap(),
             type_cache,
         ),
+        Ast::MemberAccess(member_access) => eval(
+            member_access
+                .resolved_declaration
+                .borrow()
+                .as_ref()
+                .unwrap(),
+            type_cache,
+        ),
         Ast::Integer(integer) => match integer
             .resolved_type
             .borrow()
@@ -103,6 +129,7 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             (8, false) => Value::U64(integer.value as _).into(),
             _ => unreachable!(),
         },
+        Ast::String(_) => unreachable!("a string literal is never constant"),
         Ast::Call(call) => {
             let operand = eval(&call.operand, type_cache);
             _ = operand;
@@ -114,9 +141,7 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             match &unary.operator {
                 UnaryOperator::Identity => operand,
                 UnaryOperator::Negation => todo!(),
-                UnaryOperator::LogicalNot => {
-                    Value::Bool(!operand.as_bool().unwrap().clone()).into()
-                }
+                UnaryOperator::LogicalNot => Value::Bool(!*operand.as_bool().unwrap()).into(),
                 UnaryOperator::PointerType => Value::Type(get_or_add_type_pointer(
                     type_cache,
                     operand.as_type().unwrap().clone(),
@@ -144,7 +169,7 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             }
         }
         Ast::If(iff) => {
-            let condition = eval(&iff.condition, type_cache).as_bool().unwrap().clone();
+            let condition = *eval(&iff.condition, type_cache).as_bool().unwrap();
             if let Some(else_expression) = &iff.else_expression {
                 if condition {
                     eval(&iff.then_expression, type_cache)
@@ -159,10 +184,7 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
             }
         }
         Ast::While(whilee) => loop {
-            let condition = eval(&whilee.condition, type_cache)
-                .as_bool()
-                .unwrap()
-                .clone();
+            let condition = *eval(&whilee.condition, type_cache).as_bool().unwrap();
             if !condition {
                 break Value::Void.into();
             }
@@ -171,5 +193,47 @@ pub fn eval(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Rc<Value> {
         Ast::Cast(_) => todo!(),
         Ast::Assign(_) => todo!(),
         Ast::Builtin(builtin) => Value::Type(builtin.typ.borrow().as_ref().unwrap().clone()).into(),
+        Ast::StructType(struct_type) => Value::Type({
+            if let Some(typ) = struct_type.typ.borrow().as_ref() {
+                return Value::Type(typ.clone()).into();
+            }
+            let fields = struct_type
+                .fields
+                .iter()
+                .map(|field| {
+                    let typ = eval(&field.value, type_cache).as_type().unwrap().clone();
+                    (field.name.clone(), typ)
+                })
+                .collect();
+            let typ = add_type_struct(type_cache, fields);
+            struct_type.typ.replace(Some(typ.clone()));
+            typ
+        })
+        .into(),
+        Ast::StructLiteral(_) => unreachable!("a struct literal is never constant"),
+        Ast::ArrayType(array_type) => Value::Type({
+            let length = eval(&array_type.length, type_cache).to_integer().unwrap();
+            let element_type = eval(&array_type.element_type, type_cache)
+                .as_type()
+                .unwrap()
+                .clone();
+            get_or_add_type_array(
+                type_cache,
+                usize::try_from(length).expect("array lengths are checked when resolving"),
+                element_type,
+            )
+        })
+        .into(),
+        Ast::ArrayLiteral(_) => unreachable!("an array literal is never constant"),
+        Ast::Index(_) => unreachable!("an index is never constant"),
+        Ast::SliceType(slice_type) => Value::Type({
+            let element_type = eval(&slice_type.element_type, type_cache)
+                .as_type()
+                .unwrap()
+                .clone();
+            get_or_add_type_slice(type_cache, element_type)
+        })
+        .into(),
+        Ast::Slice(_) => unreachable!("a slice is never constant"),
     }
 }
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+
+use crate::{
+    parse_file, Lexer, ParsingError, SourceLoader, SourceMap, SourceSpan, TokenKind, Trivia,
+    TriviaKind,
+};
+
+const INDENT: &str = "    ";
+/// Lines longer than this get their procedure parameters one per line.
+const MAX_WIDTH: usize = 100;
+
+/// A token or a comment, comments have no kind.
+struct Piece<'a> {
+    kind: Option<TokenKind>,
+    text: &'a str,
+}
+
+/// Formats a file by only changing the whitespace between its tokens, so comments are kept.
+/// The file is parsed first so a file with syntax errors is never rewritten, the files it
+/// imports are not read. The spans of the errors point into `source_map`.
+///
+/// Every scope and unclosed parenthesis indents its lines by four spaces, tokens are
+/// separated by single spaces except around brackets, commas, colons and unary operators,
+/// runs of blank lines become one (none at the start or end of a scope) and a procedure
+/// signature longer than the line width gets one parameter per line.
+pub fn format_source(
+    filepath: &str,
+    source: &str,
+    source_map: &mut SourceMap,
+) -> Result<String, Vec<ParsingError>> {
+    parse_file(
+        filepath,
+        source,
+        source_map,
+        &mut HashMap::new(),
+        &EmptyImports,
+    )?;
+    // only the offsets of the tokens are used, so they do not need to be in `source_map`
+    let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+    lexer.trivia = true;
+    let text = |location: &SourceSpan| &source[location.start..location.end];
+    let comments = |trivia: &[Trivia]| {
+        trivia
+            .iter()
+            .filter(|trivia| trivia.kind == TriviaKind::Comment)
+            .map(|trivia| Piece {
+                kind: None,
+                text: text(&trivia.location),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut lines = vec![];
+    let mut line = vec![];
+    loop {
+        let token = lexer.next_token().map_err(|error| vec![error.into()])?;
+        line.extend(comments(&token.leading_trivia));
+        match token.kind {
+            TokenKind::Newline => lines.push(std::mem::take(&mut line)),
+            TokenKind::EndOfFile => break,
+            _ => {
+                line.push(Piece {
+                    kind: Some(token.kind),
+                    text: text(&token.location),
+                });
+                line.extend(comments(&token.trailing_trivia));
+            }
+        }
+    }
+    lines.push(line);
+
+    let mut output = String::new();
+    let mut depth = 0usize;
+    let mut blank_line = false;
+    let mut opened_scope = false;
+    for line in &lines {
+        if line.is_empty() {
+            blank_line = !output.is_empty();
+            continue;
+        }
+        let leading_closers = line
+            .iter()
+            .take_while(|piece| piece.kind.as_ref().is_some_and(is_closer))
+            .count();
+        // no blank lines at the start or end of a scope
+        if blank_line && !opened_scope && leading_closers == 0 {
+            output.push('\n');
+        }
+        blank_line = false;
+        opened_scope = line.last().unwrap().kind.as_ref().is_some_and(is_opener);
+        format_line(line, depth.saturating_sub(leading_closers), &mut output);
+        for kind in line.iter().filter_map(|piece| piece.kind.as_ref()) {
+            if is_opener(kind) {
+                depth += 1;
+            } else if is_closer(kind) {
+                depth = depth.saturating_sub(1);
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Every import is an empty file, only the syntax of the formatted file matters.
+struct EmptyImports;
+
+impl SourceLoader for EmptyImports {
+    fn find(&self, _importer: &str, filepath: &str) -> Result<String, std::io::Error> {
+        Ok(filepath.into())
+    }
+
+    fn key(&self, filepath: &str) -> String {
+        filepath.into()
+    }
+
+    fn load(&self, _filepath: &str) -> Result<String, std::io::Error> {
+        Ok(String::new())
+    }
+}
+
+fn format_line(line: &[Piece], depth: usize, output: &mut String) {
+    let joined = join(line);
+    if INDENT.len() * depth + joined.chars().count() > MAX_WIDTH {
+        if let Some((open, close)) = find_parameter_list(line) {
+            push_line(output, depth, &join(&line[..=open]));
+            for parameter in split_parameters(&line[open + 1..close]) {
+                push_line(output, depth + 1, &(join(parameter) + ","));
+            }
+            push_line(output, depth, &join(&line[close..]));
+            return;
+        }
+    }
+    push_line(output, depth, &joined);
+}
+
+fn push_line(output: &mut String, depth: usize, line: &str) {
+    output.push_str(&INDENT.repeat(depth));
+    output.push_str(line);
+    output.push('\n');
+}
+
+/// The parentheses around the parameters of the first procedure on the line.
+fn find_parameter_list(line: &[Piece]) -> Option<(usize, usize)> {
+    let proc_index = line
+        .iter()
+        .position(|piece| piece.kind == Some(TokenKind::ProcKeyword))?;
+    let open = proc_index
+        + line[proc_index..]
+            .iter()
+            .position(|piece| piece.kind == Some(TokenKind::OpenParenthesis))?;
+    let mut nesting = 0;
+    for (index, piece) in line.iter().enumerate().skip(open) {
+        match &piece.kind {
+            Some(kind) if is_opener(kind) => nesting += 1,
+            Some(kind) if is_closer(kind) => {
+                nesting -= 1;
+                if nesting == 0 {
+                    return (index > open + 1).then_some((open, index));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_parameters<'a, 'b>(pieces: &'b [Piece<'a>]) -> Vec<&'b [Piece<'a>]> {
+    let mut parameters = vec![];
+    let mut start = 0;
+    let mut nesting = 0;
+    for (index, piece) in pieces.iter().enumerate() {
+        match &piece.kind {
+            Some(kind) if is_opener(kind) => nesting += 1,
+            Some(kind) if is_closer(kind) => nesting -= 1,
+            Some(TokenKind::Comma) if nesting == 0 => {
+                parameters.push(&pieces[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parameters.push(&pieces[start..]);
+    parameters.retain(|parameter| !parameter.is_empty());
+    parameters
+}
+
+fn join(pieces: &[Piece]) -> String {
+    let mut text = String::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        if index > 0 && space_between(&pieces[..index], piece) {
+            text.push(' ');
+        }
+        text.push_str(piece.text);
+    }
+    text
+}
+
+fn space_between(before: &[Piece], next: &Piece) -> bool {
+    let (previous, next) = match (&before.last().unwrap().kind, &next.kind) {
+        (Some(previous), Some(next)) => (previous, next),
+        _ => return true,
+    };
+    match (previous, next) {
+        (TokenKind::OpenParenthesis | TokenKind::OpenBracket, _)
+        | (_, TokenKind::CloseParenthesis | TokenKind::CloseBracket) => false,
+        (_, TokenKind::Comma | TokenKind::Colon) => false,
+        (TokenKind::Period | TokenKind::DotDot, _) | (_, TokenKind::DotDot) => false,
+        // a `.` without an operand before it starts an array literal
+        (_, TokenKind::Period) => !ends_operand(before),
+        // the length of an array type like `[4]u8` sticks to the element type
+        (TokenKind::CloseBracket, _) if closes_array_length(before) => false,
+        (TokenKind::Plus | TokenKind::Minus | TokenKind::Ampersand | TokenKind::Caret, _)
+            if !ends_operand(&before[..before.len() - 1]) =>
+        {
+            false
+        }
+        // a dereference like `ptr^`
+        (_, TokenKind::Caret) => !ends_operand(before),
+        (TokenKind::CastKeyword | TokenKind::ProcKeyword, TokenKind::OpenParenthesis) => false,
+        // the names imported by `#import "path" (a, b)`
+        (TokenKind::String, TokenKind::OpenParenthesis) if follows_import(before) => true,
+        (_, TokenKind::OpenParenthesis | TokenKind::OpenBracket) => !ends_operand(before),
+        _ => true,
+    }
+}
+
+/// Whether the pieces end with something an operator can follow, which tells a unary `-`,
+/// `&` or `^` from a binary or postfix one.
+fn ends_operand(pieces: &[Piece]) -> bool {
+    match pieces.split_last() {
+        Some((piece, rest)) => match &piece.kind {
+            None => ends_operand(rest),
+            Some(TokenKind::Caret) => ends_operand(rest),
+            Some(
+                TokenKind::Name | TokenKind::Integer | TokenKind::String | TokenKind::Character,
+            ) => true,
+            Some(TokenKind::CloseParenthesis) => !closes_prefix(pieces),
+            Some(TokenKind::CloseBracket) => !closes_array_length(pieces),
+            Some(_) => false,
+        },
+        None => false,
+    }
+}
+
+/// Whether the string the pieces end with is the path of an `#import`.
+fn follows_import(pieces: &[Piece]) -> bool {
+    pieces[..pieces.len() - 1]
+        .iter()
+        .rev()
+        .find_map(|piece| piece.kind.as_ref())
+        == Some(&TokenKind::ImportDirective)
+}
+
+/// Whether the `]` the pieces end with closes the length of an array type rather than an index
+/// or an array literal, both of which follow an operand or a `.`.
+fn closes_array_length(pieces: &[Piece]) -> bool {
+    let mut depth = 0usize;
+    for (index, piece) in pieces.iter().enumerate().rev() {
+        match &piece.kind {
+            Some(TokenKind::CloseBracket) => depth += 1,
+            Some(TokenKind::OpenBracket) => {
+                depth -= 1;
+                if depth == 0 {
+                    let before = &pieces[..index];
+                    return !ends_operand(before)
+                        && before.last().and_then(|piece| piece.kind.as_ref())
+                            != Some(&TokenKind::Period);
+                }
+            }
+            _ => {}
+        }
+    }
+    // the `[` is on an earlier line, only array literals span lines
+    false
+}
+
+/// Whether the `)` the pieces end with closes `cast(...)` or the parameters of a procedure
+/// type, which come before their operand or return type rather than being an operand.
+fn closes_prefix(pieces: &[Piece]) -> bool {
+    let mut depth = 0usize;
+    for (index, piece) in pieces.iter().enumerate().rev() {
+        match &piece.kind {
+            Some(TokenKind::CloseParenthesis) => depth += 1,
+            Some(TokenKind::OpenParenthesis) => {
+                depth -= 1;
+                if depth == 0 {
+                    return matches!(
+                        pieces[..index].last().and_then(|piece| piece.kind.as_ref()),
+                        Some(TokenKind::CastKeyword | TokenKind::ProcKeyword)
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+fn is_opener(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::OpenParenthesis | TokenKind::OpenBrace | TokenKind::OpenBracket
+    )
+}
+
+fn is_closer(kind: &TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::CloseParenthesis | TokenKind::CloseBrace | TokenKind::CloseBracket
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format_source, ParsingError, SourceMap};
+
+    #[test]
+    fn formatted_files_are_unchanged() {
+        for (filepath, source) in [
+            ("basic.lang", include_str!("../basic.lang")),
+            ("test.lang", include_str!("../test.lang")),
+        ] {
+            assert_eq!(
+                format_source(filepath, source, &mut SourceMap::new()).unwrap(),
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn unparsable_files_are_not_formatted() {
+        let errors =
+            format_source("format.lang", "proc  f( {\n}\n", &mut SourceMap::new()).unwrap_err();
+        assert!(matches!(errors[0], ParsingError::ExpectedToken { .. }));
+    }
+
+    #[test]
+    fn format() {
+        let source = "\
+\n\n#import   \"./basic.lang\"
+proc   long_signature(first_parameter: ^u64,second_parameter: u64,  third: proc(u8, u8) => bool)=>void{
+if-first_parameter^==second_parameter*2{// compare
+  /* keep */ print_u64( cast(u8)second_parameter )
+}else   {
+\tfirst_parameter^<-&second_parameter
+
+
+
+}
+}
+";
+        let formatted = format_source("format.lang", source, &mut SourceMap::new()).unwrap();
+        assert_eq!(
+            formatted,
+            "\
+#import \"./basic.lang\"
+proc long_signature(
+    first_parameter: ^u64,
+    second_parameter: u64,
+    third: proc(u8, u8) => bool,
+) => void {
+    if -first_parameter^ == second_parameter * 2 { // compare
+        /* keep */ print_u64(cast(u8) second_parameter)
+    } else {
+        first_parameter^ <- &second_parameter
+    }
+}
+"
+        );
+        assert_eq!(
+            format_source("format.lang", &formatted, &mut SourceMap::new()).unwrap(),
+            formatted
+        );
+
+        let source = "let a : [ 3 ]^[2]u8= . [ x [1] , ptr^[ 0 ]^ ]
+let b = cast(^ u8)a [0]+a[ 1 ][ 2 ]+a [1 .. 2 ] . len
+let d = cast(s8)-1-cast(s8)(2)
+let s : [ ] u8 = a[0..a.len]
+let c = .[
+1,
+    2]
+";
+        let formatted = format_source("format.lang", source, &mut SourceMap::new()).unwrap();
+        assert_eq!(
+            formatted,
+            "let a: [3]^[2]u8 = .[x[1], ptr^[0]^]
+let b = cast(^u8) a[0] + a[1][2] + a[1..2].len
+let d = cast(s8) -1 - cast(s8) (2)
+let s: []u8 = a[0..a.len]
+let c = .[
+    1,
+    2]
+"
+        );
+        assert_eq!(
+            format_source("format.lang", &formatted, &mut SourceMap::new()).unwrap(),
+            formatted
+        );
+    }
+
+    #[test]
+    fn imports() {
+        let source = "#import   \"x.lang\"(a,b)\n#import \"x.lang\"   as   m\n";
+        let formatted = format_source("format.lang", source, &mut SourceMap::new()).unwrap();
+        assert_eq!(
+            formatted,
+            "#import \"x.lang\" (a, b)\n#import \"x.lang\" as m\n"
+        );
+        assert_eq!(
+            format_source("format.lang", &formatted, &mut SourceMap::new()).unwrap(),
+            formatted
+        );
+    }
+}
@@ -5,7 +5,10 @@ use phf::phf_map;
 use derive_more::Display;
 use enum_as_inner::EnumAsInner;
 
-use crate::{SourceLocation, SourceSpan, Token, TokenData, TokenKind};
+use crate::{
+    Diagnostic, FileId, IntegerSuffix, SourceMap, SourceSpan, Token, TokenData, TokenKind, Trivia,
+    TriviaKind,
+};
 
 #[derive(Clone, PartialEq, Debug, Display, EnumAsInner)]
 pub enum LexerError {
@@ -17,15 +20,109 @@ pub enum LexerError {
         chr: char,
         base: u128,
     },
+    #[display(fmt = "{}: Integer literal in base '{}' has no digits", location, base)]
+    MissingDigits { location: SourceSpan, base: u128 },
+    #[display(fmt = "{}: Integer literal is too big", location)]
+    IntegerTooBig { location: SourceSpan },
+    #[display(fmt = "{}: Invalid integer suffix '{}'", location, suffix)]
+    InvalidIntegerSuffix {
+        location: SourceSpan,
+        suffix: String,
+    },
+    #[display(fmt = "{}: Unterminated block comment", location)]
+    UnterminatedBlockComment { location: SourceSpan },
     #[display(fmt = "{}: Unknown directive '#{}'", location, name)]
     UnknownDirective { location: SourceSpan, name: String },
+    #[display(fmt = "{}: Unterminated string literal", location)]
+    UnterminatedString { location: SourceSpan },
+    #[display(fmt = "{}: Invalid escape sequence '{}'", location, sequence)]
+    InvalidEscape {
+        location: SourceSpan,
+        sequence: String,
+    },
+    #[display(fmt = "{}: Unterminated character literal", location)]
+    UnterminatedCharacter { location: SourceSpan },
+    #[display(
+        fmt = "{}: Character literal does not hold exactly one character",
+        location
+    )]
+    InvalidCharacter { location: SourceSpan },
+    #[display(fmt = "{}: Character '{}' does not fit in a byte", location, chr)]
+    NonAsciiCharacter { location: SourceSpan, chr: char },
+}
+
+impl LexerError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexerError::UnexpectedChar { location, chr } => {
+                Diagnostic::error(*location, format!("Unexpected character '{chr}'"))
+            }
+            LexerError::DigitTooBigForBase {
+                location,
+                chr,
+                base,
+            } => Diagnostic::error(
+                *location,
+                format!("Digit '{chr}' is too big for base '{base}'"),
+            ),
+            LexerError::MissingDigits { location, base } => Diagnostic::error(
+                *location,
+                format!("Integer literal in base '{base}' has no digits"),
+            ),
+            LexerError::IntegerTooBig { location } => {
+                Diagnostic::error(*location, "Integer literal is too big")
+                    .with_note(format!("the largest integer literal is {}", u128::MAX))
+            }
+            LexerError::InvalidIntegerSuffix { location, suffix } => {
+                Diagnostic::error(*location, format!("Invalid integer suffix '{suffix}'"))
+                    .with_note("valid suffixes are s8, s16, s32, s64, u8, u16, u32 and u64")
+            }
+            LexerError::UnterminatedBlockComment { location } => {
+                Diagnostic::error(*location, "Unterminated block comment")
+                    .with_note("block comments nest, every '/*' needs its own '*/'")
+            }
+            LexerError::UnknownDirective { location, name } => {
+                Diagnostic::error(*location, format!("Unknown directive '#{name}'"))
+            }
+            LexerError::UnterminatedString { location } => {
+                Diagnostic::error(*location, "Unterminated string literal")
+            }
+            LexerError::InvalidEscape { location, sequence } => {
+                Diagnostic::error(*location, format!("Invalid escape sequence '{sequence}'"))
+                    .with_note(
+                    "valid escapes are \\n, \\r, \\t, \\0, \\\\, \\\", \\', \\xHH and \\u{HHHHHH}",
+                )
+            }
+            LexerError::UnterminatedCharacter { location } => {
+                Diagnostic::error(*location, "Unterminated character literal")
+            }
+            LexerError::InvalidCharacter { location } => Diagnostic::error(
+                *location,
+                "Character literal does not hold exactly one character",
+            )
+            .with_note("use a string literal like \"ab\" for more than one character"),
+            LexerError::NonAsciiCharacter { location, chr } => Diagnostic::error(
+                *location,
+                format!("Character '{chr}' does not fit in a byte"),
+            )
+            .with_note(format!("give the literal a wider suffix like '{chr}'u32")),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Lexer {
-    filepath: String,
-    pub location: SourceLocation,
-    source: Rc<Vec<char>>,
+    file: FileId,
+    /// Byte offset of the next character.
+    position: usize,
+    source: Rc<str>,
+    /// The token after `position` and the offset after it, filled by `peek_token`.
+    peeked: Option<(Result<Token, LexerError>, usize)>,
+    /// Return `///` and `/** */` comments as `DocComment` tokens instead of skipping them.
+    pub doc_comments: bool,
+    /// Keep the whitespace and comments around every token in its `leading_trivia` and
+    /// `trailing_trivia`, so the file can be rebuilt from its tokens.
+    pub trivia: bool,
 }
 
 static KEYWORDS: phf::Map<&'static str, TokenKind> = phf_map! {
@@ -37,6 +134,9 @@ static KEYWORDS: phf::Map<&'static str, TokenKind> = phf_map! {
     "else" => TokenKind::ElseKeyword,
     "while" => TokenKind::WhileKeyword,
     "cast" => TokenKind::CastKeyword,
+    "as" => TokenKind::AsKeyword,
+    "pub" => TokenKind::PubKeyword,
+    "struct" => TokenKind::StructKeyword,
 };
 
 static DIRECTIVES: phf::Map<&'static str, TokenKind> = phf_map! {
@@ -54,8 +154,11 @@ static SINGLE_CHAR_TOKENS: phf::Map<char, TokenKind> = phf_map! {
     ')' => TokenKind::CloseParenthesis,
     '{' => TokenKind::OpenBrace,
     '}' => TokenKind::CloseBrace,
+    '[' => TokenKind::OpenBracket,
+    ']' => TokenKind::CloseBracket,
     ':' => TokenKind::Colon,
     ',' => TokenKind::Comma,
+    '.' => TokenKind::Period,
     '^' => TokenKind::Caret,
     '&' => TokenKind::Ampersand,
     '=' => TokenKind::Equal,
@@ -92,102 +195,320 @@ static DOUBLE_CHAR_TOKENS: phf::Map<char, phf::Map<char, TokenKind>> = phf_map!
     '-' =>phf_map! {
         '>' => TokenKind::RightArrow,
     },
+    '.' => phf_map! {
+        '.' => TokenKind::DotDot,
+    },
 };
 
 impl Lexer {
-    pub fn new(filepath: String, source: &str) -> Lexer {
+    /// Adds the file to `source_map`, which the spans of the tokens point into.
+    pub fn new(source_map: &mut SourceMap, filepath: &str, source: &str) -> Lexer {
+        let file = source_map.add(filepath, source);
         Lexer {
-            filepath,
-            location: SourceLocation {
-                position: 0,
-                line: 1,
-                column: 1,
-            },
-            source: Rc::new(source.chars().collect()),
+            source: source_map.get(file).source.clone(),
+            file,
+            position: 0,
+            peeked: None,
+            doc_comments: false,
+            trivia: false,
         }
     }
 
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
     pub fn next_token(&mut self) -> Result<Token, LexerError> {
+        if let Some((token, end)) = self.peeked.take() {
+            self.position = end;
+            return token;
+        }
+        self.scan_token()
+    }
+
+    /// Returns the next token without consuming it, the token is only scanned once no
+    /// matter how many times it is peeked.
+    pub fn peek_token(&mut self) -> Result<Token, LexerError> {
+        if let Some((token, _)) = &self.peeked {
+            return token.clone();
+        }
+        let start = self.position;
+        let token = self.scan_token();
+        self.peeked = Some((token.clone(), self.position));
+        self.position = start;
+        token
+    }
+
+    fn scan_token(&mut self) -> Result<Token, LexerError> {
+        let mut leading_trivia = vec![];
+        let mut token = match self.skip_trivia(&mut leading_trivia)? {
+            Some(doc_comment) => doc_comment,
+            None => self.scan_token_after_trivia()?,
+        };
+        if self.trivia {
+            token.leading_trivia = leading_trivia;
+            // trivia after a newline leads the token on the next line
+            if token.kind != TokenKind::Newline && token.kind != TokenKind::EndOfFile {
+                let start_position = self.position;
+                if !matches!(self.skip_trivia(&mut token.trailing_trivia), Ok(None)) {
+                    // doc comments and unterminated comments are left for the next token
+                    self.position = start_position;
+                    token.trailing_trivia.clear();
+                }
+            }
+        }
+        Ok(token)
+    }
+
+    /// Skips whitespace and comments, adding them to `trivia` if the lexer keeps trivia,
+    /// up to the next token. Returns doc comments as tokens if the lexer keeps those.
+    fn skip_trivia(&mut self, trivia: &mut Vec<Trivia>) -> Result<Option<Token>, LexerError> {
         'whitespace_loop: loop {
             if self.peek_char() == ' ' || self.peek_char() == '\t' {
-                self.next_char();
+                let start_position = self.position;
+                while self.peek_char() == ' ' || self.peek_char() == '\t' {
+                    self.next_char();
+                }
+                self.push_trivia(trivia, TriviaKind::Whitespace, start_position);
                 continue 'whitespace_loop;
             }
 
             if self.peek_char() == '/' {
-                let old_location = self.location.clone();
+                let old_position = self.position;
                 self.next_char();
                 if self.peek_char() == '*' {
                     self.next_char();
+                    let opening_end = self.position;
+                    // `/**/` is an empty comment, not an empty doc comment
+                    let is_doc_comment = self.peek_char() == '*' && self.peek_second_char() != '/';
+                    let mut text = String::new();
                     let mut depth = 1;
                     while depth != 0 {
                         let chr = self.next_char();
-                        if chr == '*' && self.peek_char() == '/' {
+                        if chr == '\0' {
+                            return Err(LexerError::UnterminatedBlockComment {
+                                location: SourceSpan {
+                                    file: self.file,
+                                    start: old_position,
+                                    end: opening_end,
+                                },
+                            });
+                        } else if chr == '*' && self.peek_char() == '/' {
                             self.next_char();
                             depth -= 1;
+                            if depth != 0 {
+                                text.push_str("*/");
+                            }
                         } else if chr == '/' && self.peek_char() == '*' {
                             self.next_char();
                             depth += 1;
+                            text.push_str("/*");
+                        } else {
+                            text.push(chr);
                         }
                     }
+                    if is_doc_comment && self.doc_comments {
+                        return Ok(Some(Token {
+                            kind: TokenKind::DocComment,
+                            location: SourceSpan {
+                                file: self.file,
+                                start: old_position,
+                                end: self.position,
+                            },
+                            data: TokenData::String(text[1..].to_string()),
+                            leading_trivia: vec![],
+                            trailing_trivia: vec![],
+                        }));
+                    }
+                    self.push_trivia(trivia, TriviaKind::Comment, old_position);
                     continue 'whitespace_loop;
                 } else if self.peek_char() == '/' {
                     self.next_char();
-                    while self.peek_char() != '\r' && self.peek_char() != '\n' {
-                        self.next_char();
+                    let is_doc_comment = self.peek_char() == '/' && self.peek_second_char() != '/';
+                    let mut text = String::new();
+                    while self.peek_char() != '\r'
+                        && self.peek_char() != '\n'
+                        && self.peek_char() != '\0'
+                    {
+                        text.push(self.next_char());
+                    }
+                    if is_doc_comment && self.doc_comments {
+                        return Ok(Some(Token {
+                            kind: TokenKind::DocComment,
+                            location: SourceSpan {
+                                file: self.file,
+                                start: old_position,
+                                end: self.position,
+                            },
+                            data: TokenData::String(text[1..].to_string()),
+                            leading_trivia: vec![],
+                            trailing_trivia: vec![],
+                        }));
                     }
+                    self.push_trivia(trivia, TriviaKind::Comment, old_position);
                     continue 'whitespace_loop;
                 } else {
-                    self.location = old_location;
+                    self.position = old_position;
                 }
             }
 
-            break 'whitespace_loop;
+            return Ok(None);
         }
+    }
 
-        let start_location = self.location.clone();
+    fn push_trivia(&self, trivia: &mut Vec<Trivia>, kind: TriviaKind, start: usize) {
+        if self.trivia {
+            trivia.push(Trivia {
+                kind,
+                location: SourceSpan {
+                    file: self.file,
+                    start,
+                    end: self.position,
+                },
+            });
+        }
+    }
+
+    fn scan_token_after_trivia(&mut self) -> Result<Token, LexerError> {
+        let start_position = self.position;
         if self.peek_char().is_alphabetic() || self.peek_char() == '_' {
-            let mut name = String::new();
             while self.peek_char().is_alphanumeric() || self.peek_char() == '_' {
-                name.push(self.next_char());
+                self.next_char();
             }
-            if KEYWORDS.contains_key(&name) {
+            let name = &self.source[start_position..self.position];
+            if let Some(kind) = KEYWORDS.get(name) {
                 Ok(Token {
-                    kind: KEYWORDS.get(&name).unwrap().clone(),
+                    kind: kind.clone(),
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     data: TokenData::None,
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
                 })
             } else {
                 Ok(Token {
                     kind: TokenKind::Name,
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
-                    data: TokenData::String(name),
+                    data: TokenData::String(name.into()),
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
                 })
             }
         } else if self.peek_char() == '"' {
             self.next_char();
             let mut string = String::new();
-            while self.peek_char() != '\0' && self.peek_char() != '"' {
-                string.push(self.next_char());
+            // keep going after a bad escape so the lexer ends up after the string
+            let mut invalid_escape = None;
+            loop {
+                match self.peek_char() {
+                    '\0' => {
+                        return Err(LexerError::UnterminatedString {
+                            location: SourceSpan {
+                                file: self.file,
+                                start: start_position,
+                                end: self.position,
+                            },
+                        })
+                    }
+                    '"' => {
+                        self.next_char();
+                        break;
+                    }
+                    '\\' => match self.next_escape() {
+                        Ok(chr) => string.push(chr),
+                        Err(error) => {
+                            invalid_escape.get_or_insert(error);
+                        }
+                    },
+                    _ => string.push(self.next_char()),
+                }
+            }
+            if let Some(error) = invalid_escape {
+                return Err(error);
             }
-            assert_eq!(self.next_char(), '"');
             Ok(Token {
                 kind: TokenKind::String,
                 location: SourceSpan {
-                    filepath: self.filepath.clone(),
-                    start: start_location,
-                    end: self.location.clone(),
+                    file: self.file,
+                    start: start_position,
+                    end: self.position,
                 },
                 data: TokenData::String(string),
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
+        } else if self.peek_char() == '\'' {
+            self.next_char();
+            let chr = match self.peek_char() {
+                '\\' => Some(self.next_escape()),
+                '\'' | '\n' | '\0' => None,
+                _ => Some(Ok(self.next_char())),
+            };
+            // like strings, keep going until the closing quote so the lexer ends up after it
+            let mut extra_chars = false;
+            loop {
+                match self.peek_char() {
+                    '\'' => {
+                        self.next_char();
+                        break;
+                    }
+                    '\n' | '\0' => {
+                        return Err(LexerError::UnterminatedCharacter {
+                            location: SourceSpan {
+                                file: self.file,
+                                start: start_position,
+                                end: self.position,
+                            },
+                        })
+                    }
+                    _ => {
+                        self.next_char();
+                        extra_chars = true;
+                    }
+                }
+            }
+            let suffix = if matches!(self.peek_char(), 'u' | 's') {
+                let suffix_position = self.position;
+                let first = self.next_char();
+                Some(self.next_integer_suffix(first, suffix_position)?)
+            } else {
+                None
+            };
+            let location = SourceSpan {
+                file: self.file,
+                start: start_position,
+                end: self.position,
+            };
+            match chr {
+                // only ASCII is the same in every encoding a byte could be read as
+                Some(Ok(chr))
+                    if !extra_chars
+                        && !chr.is_ascii()
+                        && suffix.is_none_or(|suffix| suffix.size == 1) =>
+                {
+                    Err(LexerError::NonAsciiCharacter { location, chr })
+                }
+                Some(Ok(chr)) if !extra_chars => Ok(Token {
+                    kind: TokenKind::Character,
+                    location,
+                    data: TokenData::Character(chr, suffix),
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
+                }),
+                Some(Err(error)) => Err(error),
+                _ => Err(LexerError::InvalidCharacter { location }),
+            }
         } else if self.peek_char() == '#' {
             self.next_char();
             let mut name = String::new();
@@ -198,31 +519,59 @@ impl Lexer {
                 Ok(Token {
                     kind: DIRECTIVES.get(&name).unwrap().clone(),
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     data: TokenData::None,
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
                 })
             } else {
                 Err(LexerError::UnknownDirective {
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     name,
                 })
             }
         } else if self.peek_char().is_ascii_digit() {
-            let base = 10;
-            let mut value = 0;
+            let mut base = 10;
+            if self.peek_char() == '0' {
+                let prefix_base = match self.peek_second_char() {
+                    'x' => Some(16),
+                    'o' => Some(8),
+                    'b' => Some(2),
+                    _ => None,
+                };
+                if let Some(prefix_base) = prefix_base {
+                    self.next_char();
+                    self.next_char();
+                    base = prefix_base;
+                }
+            }
+            let mut value = 0u128;
+            let mut digit_count = 0;
+            let mut suffix = None;
+            let mut too_big = false;
+            // keep going after an error so the lexer ends up after the literal
+            let mut error = None;
             'parse_integer: while self.peek_char().is_ascii_alphanumeric()
                 || self.peek_char() == '_'
             {
-                let chr_location = self.location.clone();
+                let chr_position = self.position;
                 let chr = self.next_char();
                 let digit_value = match chr {
+                    // neither letter is a digit in any of the bases
+                    'u' | 's' => {
+                        match self.next_integer_suffix(chr, chr_position) {
+                            Ok(integer_suffix) => suffix = Some(integer_suffix),
+                            Err(suffix_error) => _ = error.get_or_insert(suffix_error),
+                        }
+                        break 'parse_integer;
+                    }
                     '0'..='9' => chr as u128 - '0' as u128,
                     'A'..='Z' => chr as u128 - 'A' as u128 + 10,
                     'a'..='z' => chr as u128 - 'a' as u128 + 10,
@@ -230,27 +579,59 @@ impl Lexer {
                     _ => unreachable!(),
                 };
                 if digit_value >= base {
-                    return Err(LexerError::DigitTooBigForBase {
+                    error.get_or_insert(LexerError::DigitTooBigForBase {
                         location: SourceSpan {
-                            filepath: self.filepath.clone(),
-                            start: chr_location,
-                            end: self.location.clone(),
+                            file: self.file,
+                            start: chr_position,
+                            end: self.position,
                         },
                         chr,
                         base,
                     });
+                    continue 'parse_integer;
                 }
-                value *= base;
-                value += digit_value;
+                digit_count += 1;
+                if let Some(new_value) = value
+                    .checked_mul(base)
+                    .and_then(|value| value.checked_add(digit_value))
+                {
+                    value = new_value;
+                } else {
+                    too_big = true;
+                }
+            }
+            if let Some(error) = error {
+                return Err(error);
+            }
+            if too_big {
+                return Err(LexerError::IntegerTooBig {
+                    location: SourceSpan {
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
+                    },
+                });
+            }
+            if digit_count == 0 {
+                return Err(LexerError::MissingDigits {
+                    location: SourceSpan {
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
+                    },
+                    base,
+                });
             }
             Ok(Token {
                 kind: TokenKind::Integer,
                 location: SourceSpan {
-                    filepath: self.filepath.clone(),
-                    start: start_location,
-                    end: self.location.clone(),
+                    file: self.file,
+                    start: start_position,
+                    end: self.position,
                 },
-                data: TokenData::Integer(value),
+                data: TokenData::Integer(value, suffix),
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         } else {
             let chr = self.next_char();
@@ -269,28 +650,32 @@ impl Lexer {
                         .unwrap()
                         .clone(),
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     data: TokenData::None,
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
                 })
             } else if SINGLE_CHAR_TOKENS.contains_key(&chr) {
                 Ok(Token {
                     kind: SINGLE_CHAR_TOKENS.get(&chr).unwrap().clone(),
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     data: TokenData::None,
+                    leading_trivia: vec![],
+                    trailing_trivia: vec![],
                 })
             } else {
                 Err(LexerError::UnexpectedChar {
                     location: SourceSpan {
-                        filepath: self.filepath.clone(),
-                        start: start_location,
-                        end: self.location.clone(),
+                        file: self.file,
+                        start: start_position,
+                        end: self.position,
                     },
                     chr,
                 })
@@ -298,60 +683,167 @@ impl Lexer {
         }
     }
 
-    pub fn peek_token(&self) -> Result<Token, LexerError> {
-        let mut copy = self.clone();
-        copy.next_token()
+    /// Lexes the rest of a suffix like `u8` after its first letter, which started at `start`.
+    fn next_integer_suffix(
+        &mut self,
+        first: char,
+        start: usize,
+    ) -> Result<IntegerSuffix, LexerError> {
+        let mut suffix_name = first.to_string();
+        while self.peek_char().is_ascii_alphanumeric() || self.peek_char() == '_' {
+            suffix_name.push(self.next_char());
+        }
+        let size = match &suffix_name[1..] {
+            "8" => 1,
+            "16" => 2,
+            "32" => 4,
+            "64" => 8,
+            _ => {
+                return Err(LexerError::InvalidIntegerSuffix {
+                    location: SourceSpan {
+                        file: self.file,
+                        start,
+                        end: self.position,
+                    },
+                    suffix: suffix_name,
+                })
+            }
+        };
+        Ok(IntegerSuffix {
+            size,
+            signed: first == 's',
+        })
+    }
+
+    fn next_escape(&mut self) -> Result<char, LexerError> {
+        let start_position = self.position;
+        self.next_char(); // the backslash
+        let chr = match self.peek_char() {
+            'x' => {
+                self.next_char();
+                self.next_hex_digits(2, 2)
+                    .filter(|&value| value <= 0x7F)
+                    .and_then(char::from_u32)
+            }
+            'u' => {
+                self.next_char();
+                if self.peek_char() == '{' {
+                    self.next_char();
+                    let value = self.next_hex_digits(1, 6);
+                    if self.peek_char() == '}' {
+                        self.next_char();
+                        value.and_then(char::from_u32)
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            chr => {
+                if chr != '\0' {
+                    self.next_char();
+                }
+                match chr {
+                    'n' => Some('\n'),
+                    'r' => Some('\r'),
+                    't' => Some('\t'),
+                    '0' => Some('\0'),
+                    '\\' => Some('\\'),
+                    '"' => Some('"'),
+                    '\'' => Some('\''),
+                    _ => None,
+                }
+            }
+        };
+        chr.ok_or_else(|| LexerError::InvalidEscape {
+            location: SourceSpan {
+                file: self.file,
+                start: start_position,
+                end: self.position,
+            },
+            sequence: self.source[start_position..self.position].into(),
+        })
+    }
+
+    /// Reads between `min` and `max` hex digits, `None` if there were fewer than `min`.
+    fn next_hex_digits(&mut self, min: usize, max: usize) -> Option<u32> {
+        let mut value = 0;
+        let mut count = 0;
+        while count < max {
+            if let Some(digit) = self.peek_char().to_digit(16) {
+                self.next_char();
+                value = value * 16 + digit;
+                count += 1;
+            } else {
+                break;
+            }
+        }
+        (count >= min).then_some(value)
     }
 
     fn next_char(&mut self) -> char {
         let current = self.peek_char();
         if current != '\0' {
-            self.location.position += 1;
-            self.location.column += 1;
-            if current == '\n' {
-                self.location.line += 1;
-                self.location.column = 1;
-            }
+            self.position += current.len_utf8();
         }
         current
     }
 
+    fn peek_second_char(&self) -> char {
+        self.char_at(self.position + self.peek_char().len_utf8())
+    }
+
     fn peek_char(&self) -> char {
-        if self.location.position < self.source.len() {
-            self.source[self.location.position]
-        } else {
-            '\0'
+        self.char_at(self.position)
+    }
+
+    fn char_at(&self, position: usize) -> char {
+        match self.source.as_bytes().get(position) {
+            Some(&byte) if byte.is_ascii() => byte as char,
+            Some(_) => self.source[position..].chars().next().unwrap(),
+            None => '\0',
+        }
+    }
+}
+
+/// Yields every token up to, but not including, `EndOfFile`. Errors are yielded in place
+/// of the token that failed to lex, and lexing carries on after them.
+impl Iterator for Lexer {
+    type Item = Result<Token, LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(token) if token.kind == TokenKind::EndOfFile => None,
+            result => Some(result),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Lexer, SourceLocation, SourceSpan, Token, TokenData, TokenKind};
+    use crate::{
+        IntegerSuffix, Lexer, LexerError, SourceMap, SourceSpan, Token, TokenData, TokenKind,
+        TriviaKind,
+    };
 
     #[test]
     fn empty_file() {
         let filepath = "empty.langite";
         let source = "";
-        let mut lexer = Lexer::new(filepath.to_string(), source);
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
         assert_eq!(
             lexer.next_token(),
             Ok(Token {
                 kind: TokenKind::EndOfFile,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 0,
-                        line: 1,
-                        column: 1
-                    },
-                    end: SourceLocation {
-                        position: 0,
-                        line: 1,
-                        column: 1
-                    },
+                    file: lexer.file,
+                    start: 0,
+                    end: 0,
                 },
-                data: TokenData::None
+                data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         );
     }
@@ -360,25 +852,19 @@ mod tests {
     fn single_char_tokens() {
         let filepath = "single_char_tokens.langite";
         let source = "+ - * /";
-        let mut lexer = Lexer::new(filepath.to_string(), source);
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
         assert_eq!(
             lexer.next_token(),
             Ok(Token {
                 kind: TokenKind::Plus,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 0,
-                        line: 1,
-                        column: 1
-                    },
-                    end: SourceLocation {
-                        position: 1,
-                        line: 1,
-                        column: 2
-                    }
+                    file: lexer.file,
+                    start: 0,
+                    end: 1,
                 },
                 data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         );
         assert_eq!(
@@ -386,19 +872,13 @@ mod tests {
             Ok(Token {
                 kind: TokenKind::Minus,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 2,
-                        line: 1,
-                        column: 3
-                    },
-                    end: SourceLocation {
-                        position: 3,
-                        line: 1,
-                        column: 4
-                    }
+                    file: lexer.file,
+                    start: 2,
+                    end: 3,
                 },
                 data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         );
         assert_eq!(
@@ -406,19 +886,13 @@ mod tests {
             Ok(Token {
                 kind: TokenKind::Asterisk,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 4,
-                        line: 1,
-                        column: 5
-                    },
-                    end: SourceLocation {
-                        position: 5,
-                        line: 1,
-                        column: 6
-                    }
+                    file: lexer.file,
+                    start: 4,
+                    end: 5,
                 },
                 data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         );
         assert_eq!(
@@ -426,19 +900,13 @@ mod tests {
             Ok(Token {
                 kind: TokenKind::Slash,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 6,
-                        line: 1,
-                        column: 7
-                    },
-                    end: SourceLocation {
-                        position: 7,
-                        line: 1,
-                        column: 8
-                    }
+                    file: lexer.file,
+                    start: 6,
+                    end: 7,
                 },
                 data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             })
         );
         assert_eq!(
@@ -446,20 +914,297 @@ mod tests {
             Ok(Token {
                 kind: TokenKind::EndOfFile,
                 location: SourceSpan {
-                    filepath: filepath.to_string(),
-                    start: SourceLocation {
-                        position: 7,
-                        line: 1,
-                        column: 8
-                    },
-                    end: SourceLocation {
-                        position: 7,
-                        line: 1,
-                        column: 8
-                    }
+                    file: lexer.file,
+                    start: 7,
+                    end: 7,
                 },
                 data: TokenData::None,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        let filepath = "string_escapes.langite";
+        let source = r#""a\n\t\"\\\0\x41\u{1F600}" "\q" 5"#;
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        assert_eq!(
+            lexer.next_token().map(|token| token.data),
+            Ok(TokenData::String("a\n\t\"\\\0A\u{1F600}".into()))
+        );
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::InvalidEscape { sequence, .. }) if sequence == "\\q"
+        ));
+        assert_eq!(
+            lexer.next_token().map(|token| token.data),
+            Ok(TokenData::Integer(5, None))
+        );
+    }
+
+    #[test]
+    fn character_literals() {
+        let filepath = "character_literals.langite";
+        let source = r"'a' '\n' '\'' '\u{E9}'u16 '' 'ab' '\q' 5 'é' '\u{E9}'s8 'a";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        for (chr, suffix) in [
+            ('a', None),
+            ('\n', None),
+            ('\'', None),
+            (
+                '\u{E9}',
+                Some(IntegerSuffix {
+                    size: 2,
+                    signed: false,
+                }),
+            ),
+        ] {
+            assert_eq!(
+                lexer.next_token().map(|token| token.data),
+                Ok(TokenData::Character(chr, suffix))
+            );
+        }
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::InvalidCharacter { .. })
+        ));
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::InvalidCharacter { .. })
+        ));
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::InvalidEscape { .. })
+        ));
+        assert_eq!(
+            lexer.next_token().map(|token| token.data),
+            Ok(TokenData::Integer(5, None))
+        );
+        // a byte would silently hold the Latin-1 encoding
+        for _ in 0..2 {
+            assert!(matches!(
+                lexer.next_token(),
+                Err(LexerError::NonAsciiCharacter { chr: '\u{E9}', .. })
+            ));
+        }
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::UnterminatedCharacter { .. })
+        ));
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let filepath = "unterminated_string.langite";
+        let source = "\"abc\\\"";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        assert_eq!(
+            lexer.next_token(),
+            Err(LexerError::UnterminatedString {
+                location: SourceSpan {
+                    file: lexer.file,
+                    start: 0,
+                    end: 6,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn integer_bases() {
+        let filepath = "integer_bases.langite";
+        let source = "0xFF_ff 0o17 0b1010 0 0x 0b12";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        for value in [0xFFFF, 0o17, 0b1010, 0] {
+            assert_eq!(
+                lexer.next_token().map(|token| token.data),
+                Ok(TokenData::Integer(value, None))
+            );
+        }
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::MissingDigits { base: 16, .. })
+        ));
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::DigitTooBigForBase {
+                chr: '2',
+                base: 2,
+                ..
             })
+        ));
+        assert_eq!(
+            lexer.next_token().map(|token| token.kind),
+            Ok(TokenKind::EndOfFile)
         );
     }
+
+    #[test]
+    fn integer_suffixes() {
+        let filepath = "integer_suffixes.langite";
+        let source = "255u8 0xFFs64 1_000_u32 340282366920938463463374607431768211456 5u7";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        for (value, size, signed) in [(255, 1, false), (0xFF, 8, true), (1000, 4, false)] {
+            assert_eq!(
+                lexer.next_token().map(|token| token.data),
+                Ok(TokenData::Integer(
+                    value,
+                    Some(IntegerSuffix { size, signed })
+                ))
+            );
+        }
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::IntegerTooBig { .. })
+        ));
+        assert!(matches!(
+            lexer.next_token(),
+            Err(LexerError::InvalidIntegerSuffix { suffix, .. }) if suffix == "u7"
+        ));
+        assert_eq!(
+            lexer.next_token().map(|token| token.kind),
+            Ok(TokenKind::EndOfFile)
+        );
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let filepath = "unterminated_block_comment.langite";
+        let source = "1 /* outer /* inner */\n";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        lexer.next_token().unwrap();
+        assert_eq!(
+            lexer.next_token(),
+            Err(LexerError::UnterminatedBlockComment {
+                location: SourceSpan {
+                    file: lexer.file,
+                    start: 2,
+                    end: 4,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        let filepath = "doc_comments.langite";
+        let source = "/// line\n//// not doc\n/** block /* nested */ */ /**/ // plain\n";
+        let kinds_and_data = |doc_comments| {
+            let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+            lexer.doc_comments = doc_comments;
+            let mut tokens = vec![];
+            loop {
+                let token = lexer.next_token().unwrap();
+                if token.kind == TokenKind::EndOfFile {
+                    break tokens;
+                }
+                tokens.push((token.kind, token.data));
+            }
+        };
+        assert_eq!(
+            kinds_and_data(true),
+            [
+                (TokenKind::DocComment, TokenData::String(" line".into())),
+                (TokenKind::Newline, TokenData::None),
+                (TokenKind::Newline, TokenData::None),
+                (
+                    TokenKind::DocComment,
+                    TokenData::String(" block /* nested */ ".into())
+                ),
+                (TokenKind::Newline, TokenData::None),
+            ]
+        );
+        assert_eq!(
+            kinds_and_data(false),
+            [
+                (TokenKind::Newline, TokenData::None),
+                (TokenKind::Newline, TokenData::None),
+                (TokenKind::Newline, TokenData::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn peek_token() {
+        let filepath = "peek_token.langite";
+        let source = "é_name 1";
+        let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        let peeked = lexer.peek_token();
+        assert_eq!(lexer.peek_token(), peeked);
+        assert_eq!(lexer.position(), 0);
+        assert_eq!(lexer.next_token(), peeked);
+        assert_eq!(lexer.position(), "é_name".len());
+        assert_eq!(
+            lexer.next_token().map(|token| token.data),
+            Ok(TokenData::Integer(1, None))
+        );
+    }
+
+    #[test]
+    fn iterator() {
+        let filepath = "iterator.langite";
+        let source = "a $ 1";
+        let lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+        let kinds = lexer
+            .map(|token| token.map(|token| token.kind))
+            .collect::<Vec<_>>();
+        assert!(matches!(
+            kinds.as_slice(),
+            [
+                Ok(TokenKind::Name),
+                Err(LexerError::UnexpectedChar { chr: '$', .. }),
+                Ok(TokenKind::Integer),
+            ]
+        ));
+    }
+
+    #[test]
+    fn trivia_roundtrip() {
+        let filepath = "trivia_roundtrip.langite";
+        let source = "\t/// doc\r\nproc é() /* a /* nested */\n */ {\n  x <- 1 // one\n}  ";
+        for doc_comments in [false, true] {
+            let mut lexer = Lexer::new(&mut SourceMap::new(), filepath, source);
+            lexer.doc_comments = doc_comments;
+            lexer.trivia = true;
+            let text = |location: &SourceSpan| &source[location.start..location.end];
+            let mut rebuilt = String::new();
+            let mut tokens = vec![];
+            loop {
+                let token = lexer.next_token().unwrap();
+                for trivia in token.leading_trivia.iter() {
+                    rebuilt.push_str(text(&trivia.location));
+                }
+                rebuilt.push_str(text(&token.location));
+                for trivia in token.trailing_trivia.iter() {
+                    rebuilt.push_str(text(&trivia.location));
+                }
+                if token.kind == TokenKind::EndOfFile {
+                    break;
+                }
+                tokens.push(token);
+            }
+            assert_eq!(rebuilt, source);
+            let x = tokens
+                .iter()
+                .find(|token| text(&token.location) == "x")
+                .unwrap();
+            assert_eq!(x.leading_trivia[0].kind, TriviaKind::Whitespace);
+            let one = tokens
+                .iter()
+                .find(|token| text(&token.location) == "1")
+                .unwrap();
+            assert_eq!(
+                one.trailing_trivia
+                    .iter()
+                    .map(|trivia| (trivia.kind, text(&trivia.location)))
+                    .collect::<Vec<_>>(),
+                [
+                    (TriviaKind::Whitespace, " "),
+                    (TriviaKind::Comment, "// one")
+                ]
+            );
+        }
+    }
 }
@@ -1,19 +1,32 @@
+#![allow(clippy::result_large_err)]
+
 mod ast;
+mod compiler;
+mod diagnostic;
 mod emit;
 mod eval;
+mod format;
 mod lexer;
+mod loader;
 mod location;
 mod parsing;
+mod prelude;
+mod printer;
 mod resolving;
 mod token;
 mod types;
 
 pub use ast::*;
+pub use compiler::*;
+pub use diagnostic::*;
 pub use emit::*;
 pub use eval::*;
+pub use format::*;
 pub use lexer::*;
+pub use loader::*;
 pub use location::*;
 pub use parsing::*;
+pub use prelude::*;
 pub use resolving::*;
 pub use token::*;
 pub use types::*;
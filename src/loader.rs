@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// Where the parser gets the source of every file it is asked to parse.
+pub trait SourceLoader {
+    /// The path of the file that `#import "filepath"` refers to inside `importer`.
+    fn find(&self, importer: &str, filepath: &str) -> Result<String, std::io::Error>;
+
+    /// A key that is the same for every path that refers to the same file, so that a file
+    /// reached through different paths is only imported once.
+    fn key(&self, filepath: &str) -> String;
+
+    fn load(&self, filepath: &str) -> Result<String, std::io::Error>;
+}
+
+fn not_found(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, message)
+}
+
+fn importer_directory(importer: &str) -> &Path {
+    Path::new(importer).parent().unwrap_or(Path::new(""))
+}
+
+/// Loads files from disk, looking for imports next to the importing file first and then
+/// in each of the `search_paths` in order.
+#[derive(Clone, Debug, Default)]
+pub struct FileSystemLoader {
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl FileSystemLoader {
+    pub fn new() -> FileSystemLoader {
+        FileSystemLoader::default()
+    }
+
+    pub fn add_search_path(&mut self, path: impl Into<PathBuf>) -> &mut FileSystemLoader {
+        self.search_paths.push(path.into());
+        self
+    }
+}
+
+impl SourceLoader for FileSystemLoader {
+    fn find(&self, importer: &str, filepath: &str) -> Result<String, std::io::Error> {
+        std::iter::once(importer_directory(importer))
+            .chain(self.search_paths.iter().map(PathBuf::as_path))
+            .map(|directory| directory.join(filepath))
+            .find(|path| path.is_file())
+            .map(|path| path.to_string_lossy().into_owned())
+            .ok_or_else(|| not_found("not found next to the importing file or in any search path"))
+    }
+
+    fn key(&self, filepath: &str) -> String {
+        // sources that are not on disk keep the path they were given
+        std::fs::canonicalize(filepath)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| filepath.into())
+    }
+
+    fn load(&self, filepath: &str) -> Result<String, std::io::Error> {
+        std::fs::read_to_string(filepath)
+    }
+}
+
+/// Loads files from a map of paths to sources, for compiling buffers that are not saved
+/// to disk. Paths are normalized without touching the file system, so `./a/../b.lang`
+/// and `b.lang` are the same file.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryLoader {
+    files: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> MemoryLoader {
+        MemoryLoader::default()
+    }
+
+    pub fn add_file(
+        &mut self,
+        filepath: impl AsRef<str>,
+        source: impl Into<String>,
+    ) -> &mut MemoryLoader {
+        self.files
+            .insert(normalize(Path::new(filepath.as_ref())), source.into());
+        self
+    }
+
+    pub fn get(&self, filepath: &str) -> Option<&str> {
+        self.files
+            .get(&normalize(Path::new(filepath)))
+            .map(String::as_str)
+    }
+}
+
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+impl SourceLoader for MemoryLoader {
+    fn find(&self, importer: &str, filepath: &str) -> Result<String, std::io::Error> {
+        let relative = normalize(&importer_directory(importer).join(filepath));
+        if self.files.contains_key(&relative) {
+            Ok(relative)
+        } else {
+            Err(not_found("no such file in memory"))
+        }
+    }
+
+    fn key(&self, filepath: &str) -> String {
+        normalize(Path::new(filepath))
+    }
+
+    fn load(&self, filepath: &str) -> Result<String, std::io::Error> {
+        self.get(filepath)
+            .map(Into::into)
+            .ok_or_else(|| not_found("no such file in memory"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemoryLoader, SourceLoader};
+
+    #[test]
+    fn memory_paths() {
+        let mut loader = MemoryLoader::new();
+        loader.add_file("./src/b.lang", "let b = 1\n");
+        assert_eq!(loader.find("src/a.lang", "./b.lang").unwrap(), "src/b.lang");
+        assert_eq!(
+            loader.find("src/c/a.lang", "../b.lang").unwrap(),
+            "src/b.lang"
+        );
+        assert!(loader.find("a.lang", "b.lang").is_err());
+        assert_eq!(loader.key("src/../src/b.lang"), loader.key("src/b.lang"));
+        assert_eq!(loader.load("src/b.lang").unwrap(), "let b = 1\n");
+    }
+}
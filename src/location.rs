@@ -1,3 +1,5 @@
+use std::{fmt::Display, rc::Rc};
+
 use derive_more::Display;
 
 #[derive(Clone, PartialEq, Debug, Display)]
@@ -8,26 +10,149 @@ pub struct SourceLocation {
     pub column: usize,
 }
 
-#[derive(Clone, PartialEq, Debug, Display)]
-#[display(fmt = "{}:{}", filepath, start)]
+/// A file registered in a `SourceMap`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display(fmt = "#{}", _0)]
+pub struct FileId(u32);
+
+impl FileId {
+    /// The empty file builtins are declared in, every `SourceMap` starts with it.
+    pub const BUILTIN: FileId = FileId(0);
+}
+
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: Rc<str>,
+    pub source: Rc<str>,
+    /// Byte offset of the start of every line.
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(path: &str, source: &str) -> SourceFile {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        SourceFile {
+            path: path.into(),
+            source: source.into(),
+            line_starts,
+        }
+    }
+
+    /// The line and column of a byte offset, columns count characters.
+    pub fn location(&self, position: usize) -> SourceLocation {
+        let line = self.line_starts.partition_point(|&start| start <= position);
+        let line_start = self.line_starts[line - 1];
+        let column = self
+            .source
+            .get(line_start..position)
+            .map_or(position - line_start, |text| text.chars().count());
+        SourceLocation {
+            position,
+            line,
+            column: column + 1,
+        }
+    }
+}
+
+/// Every file one compiler has read, so that spans only have to carry a `FileId`.
+///
+/// Cloning the map is cheap, the files are shared.
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    files: Vec<Rc<SourceFile>>,
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap {
+            files: vec![Rc::new(SourceFile::new("builtin.lang", ""))],
+        }
+    }
+
+    pub fn add(&mut self, path: &str, source: &str) -> FileId {
+        self.files.push(Rc::new(SourceFile::new(path, source)));
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    pub fn get(&self, file: FileId) -> &Rc<SourceFile> {
+        &self.files[file.0 as usize]
+    }
+
+    pub fn filepath(&self, span: &SourceSpan) -> &str {
+        &self.get(span.file).path
+    }
+
+    pub fn start_location(&self, span: &SourceSpan) -> SourceLocation {
+        self.get(span.file).location(span.start)
+    }
+
+    pub fn end_location(&self, span: &SourceSpan) -> SourceLocation {
+        self.get(span.file).location(span.end)
+    }
+
+    /// Shows a span as `path:line:column` of its start.
+    pub fn display<'a>(&'a self, span: &SourceSpan) -> impl Display + 'a {
+        format!("{}:{}", self.filepath(span), self.start_location(span))
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> SourceMap {
+        SourceMap::new()
+    }
+}
+
+/// Without the `SourceMap` a span can only show its file id and byte offsets, use
+/// `SourceMap::display` to show its path, line and column.
+#[derive(Clone, Copy, PartialEq, Debug, Display)]
+#[display(fmt = "{}:{}..{}", file, start, end)]
 pub struct SourceSpan {
-    pub filepath: String,
-    pub start: SourceLocation,
-    pub end: SourceLocation,
+    pub file: FileId,
+    /// Byte offsets into the file.
+    pub start: usize,
+    pub end: usize,
 }
 
 impl SourceSpan {
     pub fn combine_spans(a: &SourceSpan, b: &SourceSpan) -> SourceSpan {
-        assert_eq!(a.filepath, b.filepath);
-        assert!(a.start.position <= b.end.position);
+        assert_eq!(a.file, b.file);
+        assert!(a.start <= b.end);
         SourceSpan {
-            filepath: a.filepath.clone(),
-            start: a.start.clone(),
-            end: b.end.clone(),
+            file: a.file,
+            start: a.start,
+            end: b.end,
         }
     }
 
     pub fn get_length(&self) -> usize {
-        self.end.position - self.start.position
+        self.end - self.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FileId, SourceMap, SourceSpan};
+
+    #[test]
+    fn lazy_locations() {
+        let mut source_map = SourceMap::new();
+        let file = source_map.add("locations.lang", "let a = 1\n\tlet é = 2\n");
+        // the same text read twice is two files
+        assert_ne!(
+            source_map.add("locations.lang", "let a = 1\n\tlet é = 2\n"),
+            file
+        );
+        assert_ne!(file, FileId::BUILTIN);
+        let span = SourceSpan {
+            file,
+            start: 15,
+            end: 17,
+        };
+        assert_eq!(span.to_string(), "#1:15..17");
+        assert_eq!(source_map.display(&span).to_string(), "locations.lang:2:6");
+        assert_eq!(source_map.end_location(&span).column, 7);
+        assert_eq!(source_map.filepath(&span), "locations.lang");
     }
 }
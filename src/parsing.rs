@@ -4,10 +4,12 @@ use derive_more::Display;
 use enum_as_inner::EnumAsInner;
 
 use crate::{
-    Ast, AstAssign, AstAssignDirection, AstBinary, AstCall, AstCast, AstFile, AstIf, AstInteger,
-    AstLet, AstName, AstParameter, AstProcedure, AstProcedureBody, AstProcedureType, AstReturn,
-    AstScope, AstUnary, AstVar, AstWhile, BinaryOperator, CallingConvention, Lexer, LexerError,
-    SourceLocation, SourceSpan, Token, TokenKind, UnaryOperator,
+    Ast, AstArrayLiteral, AstArrayType, AstAssign, AstAssignDirection, AstBinary, AstCall, AstCast,
+    AstFile, AstIf, AstImport, AstImportKind, AstIndex, AstInteger, AstLet, AstMemberAccess,
+    AstName, AstParameter, AstProcedure, AstProcedureBody, AstProcedureType, AstReturn, AstScope,
+    AstSlice, AstSliceType, AstString, AstStructField, AstStructLiteral, AstStructType, AstUnary,
+    AstVar, AstWhile, BinaryOperator, CallingConvention, Diagnostic, Lexer, LexerError,
+    SourceLoader, SourceMap, SourceSpan, Token, TokenKind, UnaryOperator,
 };
 
 #[derive(Debug, Display, EnumAsInner)]
@@ -25,6 +27,10 @@ pub enum ParsingError {
     ExpectedToken { expected: TokenKind, got: Token },
     #[display(fmt = "{location}: You can only use #import at file scope")]
     ImportNotAtFileScope { location: SourceSpan },
+    #[display(fmt = "{location}: You can only use pub at file scope")]
+    PubNotAtFileScope { location: SourceSpan },
+    #[display(fmt = "{location}: You can only use pub on proc, let and var declarations")]
+    PubWithoutDeclaration { location: SourceSpan },
     #[display(fmt = "{location}: Unable to read '{filepath}': {error}")]
     UnableToReadFile {
         location: SourceSpan,
@@ -46,6 +52,50 @@ pub enum ParsingError {
     },
 }
 
+impl ParsingError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            ParsingError::LexerError(error) => error.to_diagnostic(),
+            ParsingError::UnexpectedToken { got } => {
+                Diagnostic::error(got.location, format!("Unexpected token '{}'", got.kind))
+            }
+            ParsingError::ExpectedToken { expected, got } => Diagnostic::error(
+                got.location,
+                format!("Expected '{expected}', but got '{}'", got.kind),
+            ),
+            ParsingError::ImportNotAtFileScope { location } => {
+                Diagnostic::error(*location, "You can only use #import at file scope")
+            }
+            ParsingError::PubNotAtFileScope { location } => {
+                Diagnostic::error(*location, "You can only use pub at file scope")
+            }
+            ParsingError::PubWithoutDeclaration { location } => Diagnostic::error(
+                *location,
+                "You can only use pub on proc, let and var declarations",
+            ),
+            ParsingError::UnableToReadFile {
+                location,
+                filepath,
+                error,
+            } => Diagnostic::error(*location, format!("Unable to read '{filepath}': {error}")),
+            ParsingError::CyclicImport { location, filepath } => {
+                Diagnostic::error(*location, format!("Cyclic #import of '{filepath}'"))
+            }
+            ParsingError::DuplicateCallingConvention {
+                old_convention,
+                new_location,
+                new_convention,
+            } => Diagnostic::error(
+                *new_location,
+                format!("Duplicate calling convention directive {new_convention}"),
+            )
+            .with_note(format!(
+                "the original calling convention was {old_convention}"
+            )),
+        }
+    }
+}
+
 impl From<LexerError> for ParsingError {
     fn from(error: LexerError) -> ParsingError {
         ParsingError::LexerError(error)
@@ -55,83 +105,238 @@ impl From<LexerError> for ParsingError {
 pub fn parse_file(
     filepath: &str,
     source: &str,
-    imported_files: &mut HashMap<String, bool>,
-) -> Result<Rc<AstFile>, ParsingError> {
-    imported_files.insert(filepath.into(), true);
-    let mut lexer = Lexer::new(filepath.into(), source);
+    source_map: &mut SourceMap,
+    imported_files: &mut HashMap<String, Option<Rc<AstFile>>>,
+    loader: &dyn SourceLoader,
+) -> Result<Rc<AstFile>, Vec<ParsingError>> {
+    let mut errors = vec![];
+    let file = parse_file_recovering(
+        filepath,
+        source,
+        source_map,
+        imported_files,
+        loader,
+        &mut errors,
+    );
+    if errors.is_empty() {
+        Ok(file)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses as much of the file as possible, adding every error to `errors`.
+///
+/// After an error the parser skips to the next newline (or the end of the enclosing
+/// scope) and carries on, so the returned file is missing only the expressions that
+/// failed to parse.
+///
+/// Imported files are found and read through `loader`, and every file is added to
+/// `source_map`.
+pub fn parse_file_recovering(
+    filepath: &str,
+    source: &str,
+    source_map: &mut SourceMap,
+    imported_files: &mut HashMap<String, Option<Rc<AstFile>>>,
+    loader: &dyn SourceLoader,
+    errors: &mut Vec<ParsingError>,
+) -> Rc<AstFile> {
+    let key = loader.key(filepath);
+    imported_files.insert(key.clone(), None);
+    let mut lexer = Lexer::new(source_map, filepath, source);
     let mut expressions = vec![];
-    loop {
-        allow_newlines(&mut lexer)?;
-        if lexer.peek_token()?.kind == TokenKind::EndOfFile {
-            break;
-        }
-        if lexer.peek_token()?.kind == TokenKind::ImportDirective {
-            let import_token = expect_token(&mut lexer, TokenKind::ImportDirective)?;
-            let filepath = expect_token(&mut lexer, TokenKind::String)?
-                .data
-                .into_string()
-                .unwrap();
-            if let Some(is_parsing) = imported_files.get(&filepath) {
-                if *is_parsing {
-                    return Err(ParsingError::CyclicImport {
-                        location: import_token.location.clone(),
-                        filepath,
-                    });
-                } else {
-                    continue;
-                }
-            }
-            let source = std::fs::read_to_string(filepath.clone()).map_err(|error| {
-                ParsingError::UnableToReadFile {
-                    location: import_token.location,
-                    filepath: filepath.clone(),
-                    error,
-                }
-            })?;
-            let file = parse_file(&filepath, &source, imported_files)?;
-            for expression in &file.expressions {
-                expressions.push(expression.clone());
+    let end_of_file_token = loop {
+        match parse_file_expression(
+            &mut lexer,
+            filepath,
+            source_map,
+            imported_files,
+            loader,
+            &mut expressions,
+            errors,
+        ) {
+            Ok(Some(end_of_file_token)) => break end_of_file_token,
+            Ok(None) => {}
+            Err(error) => {
+                errors.push(error);
+                synchronize(&mut lexer, false);
             }
-        } else {
-            expressions.push(parse_expression(&mut lexer)?);
         }
-        expect_newline(&mut lexer)?;
-    }
-    let end_of_file_token = expect_token(&mut lexer, TokenKind::EndOfFile)?;
-    imported_files.insert(filepath.into(), false);
-    Ok(AstFile {
+    };
+    let file: Rc<AstFile> = AstFile {
         resolving: false.into(),
         resolved_type: None.into(),
         location: SourceSpan::combine_spans(
             &SourceSpan {
-                filepath: filepath.into(),
-                start: SourceLocation {
-                    position: 0,
-                    line: 1,
-                    column: 1,
-                },
-                end: SourceLocation {
-                    position: 0,
-                    line: 1,
-                    column: 1,
-                },
+                file: lexer.file(),
+                start: 0,
+                end: 0,
             },
             &end_of_file_token.location,
         ),
         expressions,
+        names_resolved: false.into(),
     }
-    .into())
+    .into();
+    imported_files.insert(key, Some(file.clone()));
+    file
+}
+
+fn parse_file_expression(
+    lexer: &mut Lexer,
+    filepath: &str,
+    source_map: &mut SourceMap,
+    imported_files: &mut HashMap<String, Option<Rc<AstFile>>>,
+    loader: &dyn SourceLoader,
+    expressions: &mut Vec<Ast>,
+    errors: &mut Vec<ParsingError>,
+) -> Result<Option<Token>, ParsingError> {
+    allow_newlines(lexer)?;
+    if lexer.peek_token()?.kind == TokenKind::EndOfFile {
+        return Ok(Some(expect_token(lexer, TokenKind::EndOfFile)?));
+    }
+    if lexer.peek_token()?.kind == TokenKind::ImportDirective {
+        let import_token = expect_token(lexer, TokenKind::ImportDirective)?;
+        let import_path = expect_token(lexer, TokenKind::String)?
+            .data
+            .into_string()
+            .unwrap();
+        let unable_to_read = |error| ParsingError::UnableToReadFile {
+            location: import_token.location,
+            filepath: import_path.clone(),
+            error,
+        };
+        let imported_filepath = loader
+            .find(filepath, &import_path)
+            .map_err(unable_to_read)?;
+        let (file, is_first) = match imported_files.get(&loader.key(&imported_filepath)) {
+            Some(Some(file)) => (file.clone(), false),
+            Some(None) => {
+                return Err(ParsingError::CyclicImport {
+                    location: import_token.location,
+                    filepath: import_path,
+                });
+            }
+            None => {
+                let source = loader.load(&imported_filepath).map_err(unable_to_read)?;
+                let file = parse_file_recovering(
+                    &imported_filepath,
+                    &source,
+                    source_map,
+                    imported_files,
+                    loader,
+                    errors,
+                );
+                (file, true)
+            }
+        };
+        let mut location = import_token.location;
+        let kind = match lexer.peek_token()?.kind {
+            TokenKind::AsKeyword => {
+                expect_token(lexer, TokenKind::AsKeyword)?;
+                let name_token = expect_token(lexer, TokenKind::Name)?;
+                location = SourceSpan::combine_spans(&location, &name_token.location);
+                AstImportKind::Module(name_token.data.into_string().unwrap())
+            }
+            TokenKind::OpenParenthesis => {
+                expect_token(lexer, TokenKind::OpenParenthesis)?;
+                allow_newline(lexer)?;
+                let mut names = vec![];
+                while lexer.peek_token()?.kind != TokenKind::CloseParenthesis {
+                    let name_token = expect_token(lexer, TokenKind::Name)?;
+                    names.push((name_token.data.into_string().unwrap(), name_token.location));
+                    expect_comma_and_or_newline(lexer)?;
+                }
+                let close_parenthesis_token = expect_token(lexer, TokenKind::CloseParenthesis)?;
+                location = SourceSpan::combine_spans(&location, &close_parenthesis_token.location);
+                AstImportKind::Names(names)
+            }
+            _ => AstImportKind::All,
+        };
+        expressions.push(Ast::Import(
+            AstImport {
+                resolving: false.into(),
+                resolved_type: None.into(),
+                location,
+                path: import_path,
+                file,
+                kind,
+                is_first,
+            }
+            .into(),
+        ));
+    } else if lexer.peek_token()?.kind == TokenKind::PubKeyword {
+        let pub_token = expect_token(lexer, TokenKind::PubKeyword)?;
+        let mut expression = parse_expression(lexer, errors)?;
+        // the declaration was just parsed so nothing else holds on to it yet
+        match &mut expression {
+            Ast::Procedure(procedure) => Rc::get_mut(procedure).unwrap().public = true,
+            Ast::LetDeclaration(declaration) => Rc::get_mut(declaration).unwrap().public = true,
+            Ast::VarDeclaration(declaration) => Rc::get_mut(declaration).unwrap().public = true,
+            _ => {
+                return Err(ParsingError::PubWithoutDeclaration {
+                    location: SourceSpan::combine_spans(
+                        &pub_token.location,
+                        &expression.get_location(),
+                    ),
+                })
+            }
+        }
+        expressions.push(expression);
+    } else {
+        expressions.push(parse_expression(lexer, errors)?);
+    }
+    expect_newline(lexer)?;
+    Ok(None)
 }
 
-fn parse_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
-    parse_binary_expression(lexer, 0)
+/// Skips tokens until the start of the next expression, which is after the next newline
+/// that is not nested inside braces. When `inside_scope` is set the closing brace of the
+/// scope is left for the scope to consume.
+fn synchronize(lexer: &mut Lexer, inside_scope: bool) {
+    let mut depth = 0usize;
+    loop {
+        let token = match lexer.peek_token() {
+            Ok(token) => token,
+            Err(_) => {
+                // the error is either the one being recovered from or inside skipped code
+                _ = lexer.next_token();
+                continue;
+            }
+        };
+        match token.kind {
+            TokenKind::EndOfFile => return,
+            TokenKind::Newline if depth == 0 => {
+                _ = lexer.next_token();
+                return;
+            }
+            TokenKind::CloseBrace if depth == 0 && inside_scope => return,
+            TokenKind::OpenBrace => depth += 1,
+            TokenKind::CloseBrace => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        _ = lexer.next_token();
+    }
 }
 
-fn parse_least_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
-    parse_binary_expression(lexer, usize::MAX)
+fn parse_expression(
+    lexer: &mut Lexer,
+    errors: &mut Vec<ParsingError>,
+) -> Result<Ast, ParsingError> {
+    parse_binary_expression(lexer, 0, errors)
 }
 
-fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
+fn parse_least_expression(
+    lexer: &mut Lexer,
+    errors: &mut Vec<ParsingError>,
+) -> Result<Ast, ParsingError> {
+    parse_binary_expression(lexer, usize::MAX, errors)
+}
+
+fn parse_primary_expression(
+    lexer: &mut Lexer,
+    errors: &mut Vec<ParsingError>,
+) -> Result<Ast, ParsingError> {
     Ok(match lexer.peek_token()?.kind {
         TokenKind::ImportDirective => {
             return Err(ParsingError::ImportNotAtFileScope {
@@ -139,6 +344,12 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
             })
         }
 
+        TokenKind::PubKeyword => {
+            return Err(ParsingError::PubNotAtFileScope {
+                location: lexer.next_token()?.location,
+            })
+        }
+
         TokenKind::Name => {
             let token = expect_token(lexer, TokenKind::Name)?;
             Ast::Name(
@@ -154,12 +365,44 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
 
         TokenKind::Integer => {
             let token = expect_token(lexer, TokenKind::Integer)?;
+            let (value, suffix) = token.data.into_integer().unwrap();
+            Ast::Integer(
+                AstInteger {
+                    resolving: false.into(),
+                    resolved_type: None.into(),
+                    location: token.location,
+                    value,
+                    suffix,
+                    character: false,
+                }
+                .into(),
+            )
+        }
+
+        TokenKind::Character => {
+            let token = expect_token(lexer, TokenKind::Character)?;
+            let (chr, suffix) = token.data.into_character().unwrap();
             Ast::Integer(
                 AstInteger {
                     resolving: false.into(),
                     resolved_type: None.into(),
                     location: token.location,
-                    value: token.data.into_integer().unwrap(),
+                    value: chr as u128,
+                    suffix,
+                    character: true,
+                }
+                .into(),
+            )
+        }
+
+        TokenKind::String => {
+            let token = expect_token(lexer, TokenKind::String)?;
+            Ast::String(
+                AstString {
+                    resolving: false.into(),
+                    resolved_type: None.into(),
+                    location: token.location,
+                    value: token.data.into_string().unwrap(),
                 }
                 .into(),
             )
@@ -167,7 +410,7 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
 
         TokenKind::OpenParenthesis => {
             expect_token(lexer, TokenKind::OpenParenthesis)?;
-            let expression = parse_expression(lexer)?;
+            let expression = parse_expression(lexer, errors)?;
             expect_token(lexer, TokenKind::CloseParenthesis)?;
             expression
         }
@@ -179,13 +422,13 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                 allow_newline(lexer)?;
                 let mut parameter_types = vec![];
                 while lexer.peek_token()?.kind != TokenKind::CloseParenthesis {
-                    let typ = parse_expression(lexer)?;
+                    let typ = parse_expression(lexer, errors)?;
                     parameter_types.push(typ);
                     expect_comma_and_or_newline(lexer)?;
                 }
                 expect_token(lexer, TokenKind::CloseParenthesis)?;
                 expect_token(lexer, TokenKind::FatRightArrow)?;
-                let return_type = parse_least_expression(lexer)?;
+                let return_type = parse_least_expression(lexer, errors)?;
                 let mut calling_convention = None;
                 while matches!(
                     lexer.peek_token()?.kind,
@@ -259,7 +502,7 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                     };
                     let name_token = expect_token(lexer, TokenKind::Name)?;
                     expect_token(lexer, TokenKind::Colon)?;
-                    let typ = parse_least_expression(lexer)?;
+                    let typ = parse_least_expression(lexer, errors)?;
                     parameters.push(
                         AstParameter {
                             resolving: false.into(),
@@ -278,7 +521,7 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                 }
                 expect_token(lexer, TokenKind::CloseParenthesis)?;
                 expect_token(lexer, TokenKind::FatRightArrow)?;
-                let return_type = parse_least_expression(lexer)?;
+                let return_type = parse_least_expression(lexer, errors)?;
                 let mut calling_convention = None;
                 while matches!(
                     lexer.peek_token()?.kind,
@@ -335,17 +578,15 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                             ),
                         )
                     } else {
-                        let scope = parse_scope(lexer)?;
-                        (
-                            AstProcedureBody::Scope(scope.clone()),
-                            scope.location.clone(),
-                        )
+                        let scope = parse_scope(lexer, errors)?;
+                        (AstProcedureBody::Scope(scope.clone()), scope.location)
                     };
                 Ast::Procedure(
                     AstProcedure {
                         resolving: false.into(),
                         resolved_type: None.into(),
                         location: SourceSpan::combine_spans(&proc_token.location, &body_location),
+                        public: false,
                         name,
                         parameters,
                         return_type,
@@ -358,7 +599,88 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
             }
         }
 
-        TokenKind::OpenBrace => Ast::Scope(parse_scope(lexer)?),
+        TokenKind::OpenBrace => Ast::Scope(parse_scope(lexer, errors)?),
+
+        TokenKind::OpenBracket => {
+            let open_bracket_token = expect_token(lexer, TokenKind::OpenBracket)?;
+            if lexer.peek_token()?.kind == TokenKind::CloseBracket {
+                expect_token(lexer, TokenKind::CloseBracket)?;
+                let element_type = parse_least_expression(lexer, errors)?;
+                Ast::SliceType(
+                    AstSliceType {
+                        resolving: false.into(),
+                        resolved_type: None.into(),
+                        typ: None.into(),
+                        location: SourceSpan::combine_spans(
+                            &open_bracket_token.location,
+                            &element_type.get_location(),
+                        ),
+                        element_type,
+                    }
+                    .into(),
+                )
+            } else {
+                let length = parse_expression(lexer, errors)?;
+                expect_token(lexer, TokenKind::CloseBracket)?;
+                let element_type = parse_least_expression(lexer, errors)?;
+                Ast::ArrayType(
+                    AstArrayType {
+                        resolving: false.into(),
+                        resolved_type: None.into(),
+                        typ: None.into(),
+                        location: SourceSpan::combine_spans(
+                            &open_bracket_token.location,
+                            &element_type.get_location(),
+                        ),
+                        length,
+                        element_type,
+                    }
+                    .into(),
+                )
+            }
+        }
+
+        TokenKind::Period => {
+            let period_token = expect_token(lexer, TokenKind::Period)?;
+            expect_token(lexer, TokenKind::OpenBracket)?;
+            allow_newline(lexer)?;
+            let mut elements = vec![];
+            while lexer.peek_token()?.kind != TokenKind::CloseBracket {
+                elements.push(parse_expression(lexer, errors)?);
+                expect_comma_and_or_newline(lexer)?;
+            }
+            let close_bracket_token = expect_token(lexer, TokenKind::CloseBracket)?;
+            Ast::ArrayLiteral(
+                AstArrayLiteral {
+                    resolving: false.into(),
+                    resolved_type: None.into(),
+                    location: SourceSpan::combine_spans(
+                        &period_token.location,
+                        &close_bracket_token.location,
+                    ),
+                    elements,
+                }
+                .into(),
+            )
+        }
+
+        TokenKind::StructKeyword => {
+            let struct_token = expect_token(lexer, TokenKind::StructKeyword)?;
+            let (fields, close_brace_token) = parse_struct_fields(lexer, TokenKind::Colon, errors)?;
+            Ast::StructType(
+                AstStructType {
+                    resolving: false.into(),
+                    resolved_type: None.into(),
+                    typ: None.into(),
+                    location: SourceSpan::combine_spans(
+                        &struct_token.location,
+                        &close_brace_token.location,
+                    ),
+                    fields,
+                }
+                .into(),
+            )
+        }
 
         TokenKind::ReturnKeyword => {
             let return_keyword = expect_token(lexer, TokenKind::ReturnKeyword)?;
@@ -369,7 +691,7 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                     | TokenKind::CloseBrace
                     | TokenKind::CloseParenthesis
             ) {
-                Some(parse_expression(lexer)?)
+                Some(parse_expression(lexer, errors)?)
             } else {
                 None
             };
@@ -385,7 +707,7 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                                 &value.get_location(),
                             )
                         })
-                        .unwrap_or_else(|| return_keyword.location.clone()),
+                        .unwrap_or_else(|| return_keyword.location),
                     value,
                 }
                 .into(),
@@ -394,11 +716,11 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
 
         TokenKind::IfKeyword => {
             let if_token = expect_token(lexer, TokenKind::IfKeyword)?;
-            let condition = parse_expression(lexer)?;
-            let then_expression = parse_expression(lexer)?;
+            let condition = parse_expression(lexer, errors)?;
+            let then_expression = parse_expression(lexer, errors)?;
             let else_expression = if lexer.peek_token()?.kind == TokenKind::ElseKeyword {
                 expect_token(lexer, TokenKind::ElseKeyword)?;
-                Some(parse_expression(lexer)?)
+                Some(parse_expression(lexer, errors)?)
             } else {
                 None
             };
@@ -423,8 +745,8 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
 
         TokenKind::WhileKeyword => {
             let while_token = expect_token(lexer, TokenKind::WhileKeyword)?;
-            let condition = parse_expression(lexer)?;
-            let then_expression = parse_expression(lexer)?;
+            let condition = parse_expression(lexer, errors)?;
+            let then_expression = parse_expression(lexer, errors)?;
             Ast::While(
                 AstWhile {
                     resolving: false.into(),
@@ -448,17 +770,18 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                 .unwrap();
             let typ = if lexer.peek_token()?.kind == TokenKind::Colon {
                 expect_token(lexer, TokenKind::Colon)?;
-                Some(parse_least_expression(lexer)?)
+                Some(parse_least_expression(lexer, errors)?)
             } else {
                 None
             };
             expect_token(lexer, TokenKind::Equal)?;
-            let value = parse_expression(lexer)?;
+            let value = parse_expression(lexer, errors)?;
             Ast::LetDeclaration(
                 AstLet {
                     resolving: false.into(),
                     resolved_type: None.into(),
                     location: SourceSpan::combine_spans(&let_token.location, &value.get_location()),
+                    public: false,
                     name,
                     typ,
                     value,
@@ -475,17 +798,18 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
                 .unwrap();
             let typ = if lexer.peek_token()?.kind == TokenKind::Colon {
                 expect_token(lexer, TokenKind::Colon)?;
-                Some(parse_least_expression(lexer)?)
+                Some(parse_least_expression(lexer, errors)?)
             } else {
                 None
             };
             expect_token(lexer, TokenKind::LeftArrow)?;
-            let value = parse_expression(lexer)?;
+            let value = parse_expression(lexer, errors)?;
             Ast::VarDeclaration(
                 AstVar {
                     resolving: false.into(),
                     resolved_type: None.into(),
                     location: SourceSpan::combine_spans(&var_token.location, &value.get_location()),
+                    public: false,
                     name,
                     typ,
                     value,
@@ -495,22 +819,38 @@ fn parse_primary_expression(lexer: &mut Lexer) -> Result<Ast, ParsingError> {
         }
 
         _ => {
-            let token = lexer.next_token()?;
+            let token = next_token_unless_terminator(lexer)?;
             return Err(ParsingError::UnexpectedToken { got: token });
         }
     })
 }
 
-fn parse_scope(lexer: &mut Lexer) -> Result<Rc<AstScope>, ParsingError> {
+fn parse_scope(
+    lexer: &mut Lexer,
+    errors: &mut Vec<ParsingError>,
+) -> Result<Rc<AstScope>, ParsingError> {
     let open_brace_token = expect_token(lexer, TokenKind::OpenBrace)?;
     let mut expressions = vec![];
     loop {
-        allow_newlines(lexer)?;
-        if lexer.peek_token()?.kind == TokenKind::CloseBrace {
-            break;
+        let result = allow_newlines(lexer).and_then(|_| {
+            if matches!(
+                lexer.peek_token()?.kind,
+                TokenKind::CloseBrace | TokenKind::EndOfFile
+            ) {
+                return Ok(true);
+            }
+            expressions.push(parse_expression(lexer, errors)?);
+            expect_newline(lexer)?;
+            Ok(false)
+        });
+        match result {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(error) => {
+                errors.push(error);
+                synchronize(lexer, true);
+            }
         }
-        expressions.push(parse_expression(lexer)?);
-        expect_newline(lexer)?;
     }
     let close_brace_token = expect_token(lexer, TokenKind::CloseBrace)?;
     Ok(AstScope {
@@ -525,9 +865,39 @@ fn parse_scope(lexer: &mut Lexer) -> Result<Rc<AstScope>, ParsingError> {
     .into())
 }
 
+/// Parses the braces of a struct type or literal, with `separator` between every field name
+/// and its type or value.
+fn parse_struct_fields(
+    lexer: &mut Lexer,
+    separator: TokenKind,
+    errors: &mut Vec<ParsingError>,
+) -> Result<(Vec<AstStructField>, Token), ParsingError> {
+    expect_token(lexer, TokenKind::OpenBrace)?;
+    allow_newline(lexer)?;
+    let mut fields = vec![];
+    while lexer.peek_token()?.kind != TokenKind::CloseBrace {
+        let name_token = expect_token(lexer, TokenKind::Name)?;
+        expect_token(lexer, separator.clone())?;
+        let value = if separator == TokenKind::Colon {
+            parse_least_expression(lexer, errors)?
+        } else {
+            parse_expression(lexer, errors)?
+        };
+        fields.push(AstStructField {
+            location: SourceSpan::combine_spans(&name_token.location, &value.get_location()),
+            name: name_token.data.into_string().unwrap(),
+            value,
+        });
+        expect_comma_and_or_newline(lexer)?;
+    }
+    let close_brace_token = expect_token(lexer, TokenKind::CloseBrace)?;
+    Ok((fields, close_brace_token))
+}
+
 fn parse_binary_expression(
     lexer: &mut Lexer,
     parent_precedence: usize,
+    errors: &mut Vec<ParsingError>,
 ) -> Result<Ast, ParsingError> {
     fn is_unary_operator(kind: TokenKind) -> bool {
         matches!(
@@ -553,9 +923,9 @@ fn parse_binary_expression(
     let mut left = if lexer.peek_token()?.kind == TokenKind::CastKeyword {
         let cast_token = expect_token(lexer, TokenKind::CastKeyword)?;
         expect_token(lexer, TokenKind::OpenParenthesis)?;
-        let typ = parse_expression(lexer)?;
+        let typ = parse_expression(lexer, errors)?;
         expect_token(lexer, TokenKind::CloseParenthesis)?;
-        let operand = parse_least_expression(lexer)?;
+        let operand = parse_least_expression(lexer, errors)?;
         Ast::Cast(
             AstCast {
                 resolving: false.into(),
@@ -576,7 +946,7 @@ fn parse_binary_expression(
                 TokenKind::Ampersand => UnaryOperator::AddressOf,
                 _ => unreachable!(),
             };
-            let operand = parse_least_expression(lexer)?;
+            let operand = parse_least_expression(lexer, errors)?;
             Ast::Unary(
                 AstUnary {
                     resolving: false.into(),
@@ -591,7 +961,7 @@ fn parse_binary_expression(
                 .into(),
             )
         } else {
-            parse_primary_expression(lexer)?
+            parse_primary_expression(lexer, errors)?
         }
     };
 
@@ -602,7 +972,7 @@ fn parse_binary_expression(
                 allow_newline(lexer)?;
                 let mut arguments = vec![];
                 while lexer.peek_token()?.kind != TokenKind::CloseParenthesis {
-                    arguments.push(parse_expression(lexer)?);
+                    arguments.push(parse_expression(lexer, errors)?);
                     expect_comma_and_or_newline(lexer)?;
                 }
                 let close_parenthesis_token = expect_token(lexer, TokenKind::CloseParenthesis)?;
@@ -624,7 +994,7 @@ fn parse_binary_expression(
             TokenKind::LeftArrow if parent_precedence == 0 => {
                 expect_token(lexer, TokenKind::LeftArrow)?;
                 allow_newline(lexer)?;
-                let value = parse_expression(lexer)?;
+                let value = parse_expression(lexer, errors)?;
                 Ast::Assign(
                     AstAssign {
                         resolving: false.into(),
@@ -644,7 +1014,7 @@ fn parse_binary_expression(
             TokenKind::RightArrow if parent_precedence == 0 => {
                 expect_token(lexer, TokenKind::RightArrow)?;
                 allow_newline(lexer)?;
-                let operand = parse_expression(lexer)?;
+                let operand = parse_expression(lexer, errors)?;
                 Ast::Assign(
                     AstAssign {
                         resolving: false.into(),
@@ -661,6 +1031,82 @@ fn parse_binary_expression(
                 )
             }
 
+            TokenKind::Period => {
+                expect_token(lexer, TokenKind::Period)?;
+                if lexer.peek_token()?.kind == TokenKind::OpenBrace {
+                    let (fields, close_brace_token) =
+                        parse_struct_fields(lexer, TokenKind::Equal, errors)?;
+                    Ast::StructLiteral(
+                        AstStructLiteral {
+                            resolving: false.into(),
+                            resolved_type: None.into(),
+                            location: SourceSpan::combine_spans(
+                                &left.get_location(),
+                                &close_brace_token.location,
+                            ),
+                            typ: left,
+                            fields,
+                        }
+                        .into(),
+                    )
+                } else {
+                    let name_token = expect_token(lexer, TokenKind::Name)?;
+                    Ast::MemberAccess(
+                        AstMemberAccess {
+                            resolving: false.into(),
+                            resolved_type: None.into(),
+                            location: SourceSpan::combine_spans(
+                                &left.get_location(),
+                                &name_token.location,
+                            ),
+                            operand: left,
+                            name: name_token.data.into_string().unwrap(),
+                            resolved_declaration: None.into(),
+                        }
+                        .into(),
+                    )
+                }
+            }
+
+            TokenKind::OpenBracket => {
+                expect_token(lexer, TokenKind::OpenBracket)?;
+                let index = parse_expression(lexer, errors)?;
+                if lexer.peek_token()?.kind == TokenKind::DotDot {
+                    expect_token(lexer, TokenKind::DotDot)?;
+                    let end = parse_expression(lexer, errors)?;
+                    let close_bracket_token = expect_token(lexer, TokenKind::CloseBracket)?;
+                    Ast::Slice(
+                        AstSlice {
+                            resolving: false.into(),
+                            resolved_type: None.into(),
+                            location: SourceSpan::combine_spans(
+                                &left.get_location(),
+                                &close_bracket_token.location,
+                            ),
+                            operand: left,
+                            start: index,
+                            end,
+                        }
+                        .into(),
+                    )
+                } else {
+                    let close_bracket_token = expect_token(lexer, TokenKind::CloseBracket)?;
+                    Ast::Index(
+                        AstIndex {
+                            resolving: false.into(),
+                            resolved_type: None.into(),
+                            location: SourceSpan::combine_spans(
+                                &left.get_location(),
+                                &close_bracket_token.location,
+                            ),
+                            operand: left,
+                            index,
+                        }
+                        .into(),
+                    )
+                }
+            }
+
             TokenKind::Caret => {
                 let caret_token = expect_token(lexer, TokenKind::Caret)?;
                 Ast::Unary(
@@ -697,7 +1143,7 @@ fn parse_binary_expression(
                     TokenKind::GreaterThanEqual => BinaryOperator::GreaterThanEqual,
                     _ => unreachable!(),
                 };
-                let right = parse_binary_expression(lexer, binary_precedence)?;
+                let right = parse_binary_expression(lexer, binary_precedence, errors)?;
                 Ast::Binary(
                     AstBinary {
                         resolving: false.into(),
@@ -736,7 +1182,7 @@ fn allow_newline(lexer: &mut Lexer) -> Result<(), ParsingError> {
 fn expect_comma_and_or_newline(lexer: &mut Lexer) -> Result<(), ParsingError> {
     let token = lexer.peek_token()?;
     match token.kind {
-        TokenKind::CloseParenthesis | TokenKind::CloseBrace => {}
+        TokenKind::CloseParenthesis | TokenKind::CloseBrace | TokenKind::CloseBracket => {}
         _ => {
             expect_token(lexer, TokenKind::Comma)?;
             allow_newline(lexer)?;
@@ -747,22 +1193,184 @@ fn expect_comma_and_or_newline(lexer: &mut Lexer) -> Result<(), ParsingError> {
 
 fn expect_newline(lexer: &mut Lexer) -> Result<(), ParsingError> {
     let token = lexer.peek_token()?;
-    Ok(match token.kind {
+    match token.kind {
         TokenKind::EndOfFile | TokenKind::CloseParenthesis | TokenKind::CloseBrace => {}
         _ => {
             expect_token(lexer, TokenKind::Newline)?;
         }
-    })
+    }
+    Ok(())
+}
+
+/// Consumes the next token, except for tokens that end an expression which are left for
+/// error recovery to synchronize on.
+fn next_token_unless_terminator(lexer: &mut Lexer) -> Result<Token, ParsingError> {
+    let token = lexer.peek_token()?;
+    if matches!(
+        token.kind,
+        TokenKind::Newline | TokenKind::CloseBrace | TokenKind::EndOfFile
+    ) {
+        Ok(token)
+    } else {
+        Ok(lexer.next_token()?)
+    }
 }
 
 fn expect_token(lexer: &mut Lexer, kind: TokenKind) -> Result<Token, ParsingError> {
-    let token = lexer.next_token()?;
-    if token.kind != kind {
+    if lexer.peek_token()?.kind != kind {
         Err(ParsingError::ExpectedToken {
             expected: kind,
-            got: token,
+            got: next_token_unless_terminator(lexer)?,
         })
     } else {
-        Ok(token)
+        Ok(lexer.next_token()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, rc::Rc};
+
+    use crate::{
+        parse_file, parse_file_recovering, Ast, FileSystemLoader, MemoryLoader, ParsingError,
+        SourceMap,
+    };
+
+    #[test]
+    fn recovers_after_errors() {
+        let source = "\
+let a = )
+proc f(x: u64) => u64 {
+    let y =
+    return x
+}
+let b = 1 2
+var c <- 3
+";
+        let mut errors = vec![];
+        let file = parse_file_recovering(
+            "recovers_after_errors.lang",
+            source,
+            &mut SourceMap::new(),
+            &mut Default::default(),
+            &MemoryLoader::new(),
+            &mut errors,
+        );
+        assert_eq!(errors.len(), 3);
+        assert!(matches!(errors[0], ParsingError::UnexpectedToken { .. }));
+        assert!(matches!(errors[1], ParsingError::UnexpectedToken { .. }));
+        assert!(matches!(errors[2], ParsingError::ExpectedToken { .. }));
+
+        assert_eq!(file.expressions.len(), 3);
+        let procedure = file.expressions[0].as_procedure().unwrap();
+        let body = procedure.body.as_scope().unwrap();
+        assert_eq!(body.expressions.len(), 1);
+        assert!(body.expressions[0].is_return());
+        assert!(matches!(&file.expressions[1], Ast::LetDeclaration(b) if b.name == "b"));
+        assert!(matches!(&file.expressions[2], Ast::VarDeclaration(c) if c.name == "c"));
+    }
+
+    #[test]
+    fn imports_relative_to_importer() {
+        let root = std::env::temp_dir().join(format!("langite-imports-{}", std::process::id()));
+        let library = root.join("library");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(&library).unwrap();
+        fs::write(
+            root.join("src/a.lang"),
+            "#import \"./b.lang\"\n#import \"b.lang\"\n#import \"shared.lang\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/b.lang"), "let b = 1\n").unwrap();
+        fs::write(library.join("shared.lang"), "let shared = 2\n").unwrap();
+
+        let main = "#import \"src/a.lang\"\n";
+        let main_path = root.join("main.lang").to_string_lossy().into_owned();
+        let mut loader = FileSystemLoader::new();
+        loader.add_search_path(library);
+        let mut source_map = SourceMap::new();
+        let file = parse_file(
+            &main_path,
+            main,
+            &mut source_map,
+            &mut HashMap::new(),
+            &loader,
+        )
+        .unwrap();
+        let imports = file.expressions[0]
+            .as_import()
+            .unwrap()
+            .file
+            .expressions
+            .clone();
+        let imports = imports
+            .iter()
+            .map(|expression| expression.as_import().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(imports.len(), 3);
+        assert!(Rc::ptr_eq(&imports[0].file, &imports[1].file));
+        assert!(imports[0].is_first && !imports[1].is_first);
+        assert!(source_map
+            .filepath(&imports[2].file.location)
+            .ends_with("shared.lang"));
+
+        let errors = parse_file(
+            &main_path,
+            main,
+            &mut SourceMap::new(),
+            &mut HashMap::new(),
+            &FileSystemLoader::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ParsingError::UnableToReadFile { filepath, .. }] if filepath == "shared.lang"
+        ));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn imports_from_memory() {
+        let mut loader = MemoryLoader::new();
+        loader
+            .add_file("src/a.lang", "#import \"../lib/b.lang\"\nlet a = 1\n")
+            .add_file("lib/b.lang", "#import \"missing.lang\"\nlet b = 2\n");
+        let mut source_map = SourceMap::new();
+        let errors = parse_file(
+            "main.lang",
+            "#import \"src/a.lang\"\n#import \"./lib/b.lang\"\n",
+            &mut source_map,
+            &mut HashMap::new(),
+            &loader,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            &errors[..],
+            [ParsingError::UnableToReadFile { filepath, location, .. }]
+                if filepath == "missing.lang" && source_map.filepath(location) == "lib/b.lang"
+        ));
+    }
+
+    #[test]
+    fn pub_declarations() {
+        let source = "pub proc f() => void {\n    pub let x = 1\n}\npub let y = 2\npub 3\n";
+        let mut errors = vec![];
+        let file = parse_file_recovering(
+            "pub_declarations.lang",
+            source,
+            &mut SourceMap::new(),
+            &mut Default::default(),
+            &MemoryLoader::new(),
+            &mut errors,
+        );
+        assert!(matches!(
+            &errors[..],
+            [
+                ParsingError::PubNotAtFileScope { .. },
+                ParsingError::PubWithoutDeclaration { .. }
+            ]
+        ));
+        assert!(file.expressions[0].as_procedure().unwrap().public);
+        assert!(file.expressions[1].as_let_declaration().unwrap().public);
     }
 }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::{AstBuiltin, AstBuiltinKind, Declaration};
+
+/// The names that are visible at the start of every program.
+///
+/// Builtins are stored by kind and a fresh `AstBuiltin` is created by every call to
+/// [`Prelude::names`], because a builtin caches its resolved type and so must not be
+/// shared between programs that use different type caches.
+#[derive(Clone, Debug)]
+pub struct Prelude {
+    builtins: Vec<(String, AstBuiltinKind)>,
+    declarations: Vec<(String, Declaration)>,
+}
+
+impl Prelude {
+    /// A prelude with no names at all.
+    pub fn empty() -> Prelude {
+        Prelude {
+            builtins: vec![],
+            declarations: vec![],
+        }
+    }
+
+    /// The standard prelude: `type`, `void`, `bool`, `string` and the integer types.
+    pub fn standard() -> Prelude {
+        let mut prelude = Prelude::empty();
+        prelude
+            .add_builtin("type", AstBuiltinKind::Type)
+            .add_builtin("void", AstBuiltinKind::Void)
+            .add_builtin("bool", AstBuiltinKind::Bool)
+            .add_builtin("string", AstBuiltinKind::String);
+        for (size, bits) in [(1, 8), (2, 16), (4, 32), (8, 64)] {
+            prelude
+                .add_builtin(
+                    format!("s{bits}"),
+                    AstBuiltinKind::IntegerType { size, signed: true },
+                )
+                .add_builtin(
+                    format!("u{bits}"),
+                    AstBuiltinKind::IntegerType {
+                        size,
+                        signed: false,
+                    },
+                );
+        }
+        prelude
+    }
+
+    /// Registers a builtin under `name`, replacing any previous name.
+    pub fn add_builtin(&mut self, name: impl Into<String>, kind: AstBuiltinKind) -> &mut Prelude {
+        let name = name.into();
+        self.remove(&name);
+        self.builtins.push((name, kind));
+        self
+    }
+
+    /// Registers an already built declaration under `name`, replacing any previous name.
+    pub fn add_declaration(
+        &mut self,
+        name: impl Into<String>,
+        declaration: Declaration,
+    ) -> &mut Prelude {
+        let name = name.into();
+        self.remove(&name);
+        self.declarations.push((name, declaration));
+        self
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.builtins
+            .retain(|(builtin_name, _)| builtin_name != name);
+        self.declarations
+            .retain(|(declaration_name, _)| declaration_name != name);
+    }
+
+    /// Builds the name table to pass to `resolve_names`.
+    pub fn names(&self) -> HashMap<String, Declaration> {
+        self.builtins
+            .iter()
+            .map(|(name, kind)| {
+                (
+                    name.clone(),
+                    Declaration::Builtin(
+                        AstBuiltin {
+                            resolving: false.into(),
+                            resolved_type: None.into(),
+                            typ: None.into(),
+                            kind: kind.clone(),
+                        }
+                        .into(),
+                    ),
+                )
+            })
+            .chain(self.declarations.iter().cloned())
+            .collect()
+    }
+}
+
+impl Default for Prelude {
+    fn default() -> Prelude {
+        Prelude::standard()
+    }
+}
+
+/// The name table of the standard prelude.
+pub fn default_names() -> HashMap<String, Declaration> {
+    Prelude::standard().names()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AstBuiltinKind, Compiler, Prelude};
+
+    #[test]
+    fn extra_builtin() {
+        let mut prelude = Prelude::standard();
+        prelude.add_builtin(
+            "int",
+            AstBuiltinKind::IntegerType {
+                size: 8,
+                signed: true,
+            },
+        );
+        Compiler::with_prelude(&prelude)
+            .compile_source("extra_builtin.lang", "var x: int <- 5\n")
+            .unwrap();
+    }
+}
@@ -0,0 +1,694 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{
+    Ast, AstAssignDirection, AstBuiltinKind, AstImportKind, AstProcedureBody, AstStructField,
+    BinaryOperator, CallingConvention, UnaryOperator,
+};
+
+/// Prints the tree back as source, every operand that is not a name, literal, call,
+/// member access, index, slice, struct or array literal is put in parentheses so the
+/// printed source parses back into the same tree.
+impl Display for Ast {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_ast(self, 0, f)
+    }
+}
+
+fn write_ast(ast: &Ast, indent: usize, f: &mut Formatter) -> Result {
+    match ast {
+        Ast::File(file) => {
+            for expression in &file.expressions {
+                write_ast(expression, indent, f)?;
+                writeln!(f)?;
+            }
+            Ok(())
+        }
+        Ast::Import(import) => {
+            write!(f, "#import {:?}", import.path)?;
+            match &import.kind {
+                AstImportKind::All => Ok(()),
+                AstImportKind::Module(name) => write!(f, " as {name}"),
+                AstImportKind::Names(names) => {
+                    write!(f, " (")?;
+                    for (i, (name, _)) in names.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{name}")?;
+                    }
+                    write!(f, ")")
+                }
+            }
+        }
+        Ast::Procedure(procedure) => {
+            if procedure.public {
+                write!(f, "pub ")?;
+            }
+            write!(f, "proc {}(", procedure.name)?;
+            for (i, parameter) in procedure.parameters.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_ast(&Ast::Parameter(parameter.clone()), indent, f)?;
+            }
+            write!(f, ") => ")?;
+            write_operand(&procedure.return_type, indent, f)?;
+            write_calling_convention(&procedure.calling_convention, f)?;
+            match &procedure.body {
+                AstProcedureBody::ExternName(name) => write!(f, " #extern {name:?}"),
+                AstProcedureBody::Scope(scope) => {
+                    write!(f, " ")?;
+                    write_ast(&Ast::Scope(scope.clone()), indent, f)
+                }
+            }
+        }
+        Ast::ProcedureType(procedure_type) => {
+            write!(f, "proc(")?;
+            for (i, parameter_type) in procedure_type.parameter_types.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_ast(parameter_type, indent, f)?;
+            }
+            write!(f, ") => ")?;
+            write_operand(&procedure_type.return_type, indent, f)?;
+            write_calling_convention(&procedure_type.calling_convention, f)
+        }
+        Ast::Parameter(parameter) => {
+            if parameter.mutable {
+                write!(f, "var ")?;
+            }
+            write!(f, "{}: ", parameter.name)?;
+            write_operand(&parameter.typ, indent, f)
+        }
+        Ast::Scope(scope) => {
+            if scope.expressions.is_empty() {
+                return write!(f, "{{}}");
+            }
+            writeln!(f, "{{")?;
+            for expression in &scope.expressions {
+                write!(f, "{}", "    ".repeat(indent + 1))?;
+                write_ast(expression, indent + 1, f)?;
+                writeln!(f)?;
+            }
+            write!(f, "{}}}", "    ".repeat(indent))
+        }
+        Ast::LetDeclaration(declaration) => {
+            if declaration.public {
+                write!(f, "pub ")?;
+            }
+            write!(f, "let {}", declaration.name)?;
+            if let Some(typ) = &declaration.typ {
+                write!(f, ": ")?;
+                write_operand(typ, indent, f)?;
+            }
+            write!(f, " = ")?;
+            write_ast(&declaration.value, indent, f)
+        }
+        Ast::VarDeclaration(declaration) => {
+            if declaration.public {
+                write!(f, "pub ")?;
+            }
+            write!(f, "var {}", declaration.name)?;
+            if let Some(typ) = &declaration.typ {
+                write!(f, ": ")?;
+                write_operand(typ, indent, f)?;
+            }
+            write!(f, " <- ")?;
+            write_ast(&declaration.value, indent, f)
+        }
+        Ast::Name(name) => write!(f, "{}", name.name),
+        Ast::MemberAccess(member_access) => {
+            write_operand(&member_access.operand, indent, f)?;
+            write!(f, ".{}", member_access.name)
+        }
+        Ast::Integer(integer) => {
+            if integer.character {
+                write!(f, "{:?}", char::from_u32(integer.value as u32).unwrap())?;
+            } else {
+                write!(f, "{}", integer.value)?;
+            }
+            if let Some(suffix) = &integer.suffix {
+                write!(f, "{suffix}")?;
+            }
+            Ok(())
+        }
+        Ast::String(string) => write!(f, "{:?}", string.value),
+        Ast::Call(call) => {
+            write_operand(&call.operand, indent, f)?;
+            write!(f, "(")?;
+            for (i, argument) in call.arguments.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_ast(argument, indent, f)?;
+            }
+            write!(f, ")")
+        }
+        Ast::Return(returnn) => {
+            write!(f, "return")?;
+            if let Some(value) = &returnn.value {
+                write!(f, " ")?;
+                write_ast(value, indent, f)?;
+            }
+            Ok(())
+        }
+        Ast::Unary(unary) => {
+            let prefix = match unary.operator {
+                UnaryOperator::Identity => "+",
+                UnaryOperator::Negation => "-",
+                // there is no syntax for it, the parser never produces it
+                UnaryOperator::LogicalNot => "!",
+                UnaryOperator::PointerType => "^",
+                UnaryOperator::AddressOf => "&",
+                UnaryOperator::Dereference => {
+                    write_operand(&unary.operand, indent, f)?;
+                    return write!(f, "^");
+                }
+            };
+            write!(f, "{prefix}")?;
+            write_operand(&unary.operand, indent, f)
+        }
+        Ast::Binary(binary) => {
+            let operator = match binary.operator {
+                BinaryOperator::Add => "+",
+                BinaryOperator::Subtract => "-",
+                BinaryOperator::Multiply => "*",
+                BinaryOperator::Divide => "/",
+                BinaryOperator::Remainder => "%",
+                BinaryOperator::Equal => "==",
+                BinaryOperator::NotEqual => "!=",
+                BinaryOperator::LessThan => "<",
+                BinaryOperator::GreaterThan => ">",
+                BinaryOperator::LessThanEqual => "<=",
+                BinaryOperator::GreaterThanEqual => ">=",
+            };
+            write_operand(&binary.left, indent, f)?;
+            write!(f, " {operator} ")?;
+            write_operand(&binary.right, indent, f)
+        }
+        Ast::If(iff) => {
+            write!(f, "if ")?;
+            write_operand(&iff.condition, indent, f)?;
+            write!(f, " ")?;
+            write_ast(&iff.then_expression, indent, f)?;
+            if let Some(else_expression) = &iff.else_expression {
+                write!(f, " else ")?;
+                write_ast(else_expression, indent, f)?;
+            }
+            Ok(())
+        }
+        Ast::While(whilee) => {
+            write!(f, "while ")?;
+            write_operand(&whilee.condition, indent, f)?;
+            write!(f, " ")?;
+            write_ast(&whilee.then_expression, indent, f)
+        }
+        Ast::Cast(cast) => {
+            write!(f, "cast(")?;
+            write_ast(&cast.typ, indent, f)?;
+            write!(f, ") ")?;
+            write_operand(&cast.operand, indent, f)
+        }
+        Ast::Assign(assign) => match assign.direction {
+            AstAssignDirection::Left => {
+                write_operand(&assign.operand, indent, f)?;
+                write!(f, " <- ")?;
+                write_operand(&assign.value, indent, f)
+            }
+            AstAssignDirection::Right => {
+                write_operand(&assign.value, indent, f)?;
+                write!(f, " -> ")?;
+                write_operand(&assign.operand, indent, f)
+            }
+        },
+        Ast::Builtin(builtin) => match &builtin.kind {
+            AstBuiltinKind::Type => write!(f, "type"),
+            AstBuiltinKind::Void => write!(f, "void"),
+            AstBuiltinKind::Bool => write!(f, "bool"),
+            AstBuiltinKind::String => write!(f, "string"),
+            AstBuiltinKind::IntegerType { size, signed } => {
+                write!(f, "{}{}", if *signed { "s" } else { "u" }, size * 8)
+            }
+        },
+        Ast::StructType(struct_type) => {
+            write!(f, "struct ")?;
+            write_fields(&struct_type.fields, ":", indent, f)
+        }
+        Ast::StructLiteral(literal) => {
+            write_operand(&literal.typ, indent, f)?;
+            write!(f, ".")?;
+            write_fields(&literal.fields, " =", indent, f)
+        }
+        Ast::ArrayType(array_type) => {
+            write!(f, "[")?;
+            write_ast(&array_type.length, indent, f)?;
+            write!(f, "]")?;
+            write_operand(&array_type.element_type, indent, f)
+        }
+        Ast::ArrayLiteral(literal) => {
+            write!(f, ".[")?;
+            for (i, element) in literal.elements.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write_ast(element, indent, f)?;
+            }
+            write!(f, "]")
+        }
+        Ast::Index(index) => {
+            write_operand(&index.operand, indent, f)?;
+            write!(f, "[")?;
+            write_ast(&index.index, indent, f)?;
+            write!(f, "]")
+        }
+        Ast::SliceType(slice_type) => {
+            write!(f, "[]")?;
+            write_operand(&slice_type.element_type, indent, f)
+        }
+        Ast::Slice(slice) => {
+            write_operand(&slice.operand, indent, f)?;
+            write!(f, "[")?;
+            write_ast(&slice.start, indent, f)?;
+            write!(f, "..")?;
+            write_ast(&slice.end, indent, f)?;
+            write!(f, "]")
+        }
+    }
+}
+
+fn write_fields(
+    fields: &[AstStructField],
+    separator: &str,
+    indent: usize,
+    f: &mut Formatter,
+) -> Result {
+    if fields.is_empty() {
+        return write!(f, "{{}}");
+    }
+    write!(f, "{{ ")?;
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}{separator} ", field.name)?;
+        // struct types take single terms like parameters do
+        if separator == ":" {
+            write_operand(&field.value, indent, f)?;
+        } else {
+            write_ast(&field.value, indent, f)?;
+        }
+    }
+    write!(f, " }}")
+}
+
+/// Writes an operand of another expression, in parentheses unless it is a single term.
+fn write_operand(ast: &Ast, indent: usize, f: &mut Formatter) -> Result {
+    if matches!(
+        ast,
+        Ast::Name(_)
+            | Ast::Integer(_)
+            | Ast::String(_)
+            | Ast::Call(_)
+            | Ast::MemberAccess(_)
+            | Ast::Builtin(_)
+            | Ast::StructType(_)
+            | Ast::StructLiteral(_)
+            | Ast::ArrayLiteral(_)
+            | Ast::Index(_)
+            | Ast::Slice(_)
+    ) {
+        write_ast(ast, indent, f)
+    } else {
+        write!(f, "(")?;
+        write_ast(ast, indent, f)?;
+        write!(f, ")")
+    }
+}
+
+fn write_calling_convention(calling_convention: &CallingConvention, f: &mut Formatter) -> Result {
+    match calling_convention {
+        CallingConvention::CDecl => Ok(()),
+        calling_convention => write!(f, " {calling_convention}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_file, Ast, AstProcedureBody, AstStructField, MemoryLoader, SourceMap};
+
+    fn parse(source: &str) -> Ast {
+        let mut loader = MemoryLoader::new();
+        loader.add_file("lib.lang", "pub let a = 1\n");
+        match parse_file(
+            "printer.lang",
+            source,
+            &mut SourceMap::new(),
+            &mut Default::default(),
+            &loader,
+        ) {
+            Ok(file) => Ast::File(file),
+            Err(errors) => panic!("{source}\n{errors:?}"),
+        }
+    }
+
+    fn same_list(a: &[Ast], b: &[Ast]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+    }
+
+    fn same_option(a: &Option<Ast>, b: &Option<Ast>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => same(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+
+    fn same_fields(a: &[AstStructField], b: &[AstStructField]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| a.name == b.name && same(&a.value, &b.value))
+    }
+
+    /// Compares everything but locations and resolution results.
+    fn same(a: &Ast, b: &Ast) -> bool {
+        match (a, b) {
+            (Ast::File(a), Ast::File(b)) => same_list(&a.expressions, &b.expressions),
+            (Ast::Import(a), Ast::Import(b)) => {
+                a.path == b.path
+                    && match (&a.kind, &b.kind) {
+                        (crate::AstImportKind::Names(a), crate::AstImportKind::Names(b)) => a
+                            .iter()
+                            .map(|name| &name.0)
+                            .eq(b.iter().map(|name| &name.0)),
+                        (a, b) => a == b,
+                    }
+            }
+            (Ast::Procedure(a), Ast::Procedure(b)) => {
+                a.public == b.public
+                    && a.name == b.name
+                    && a.parameters.len() == b.parameters.len()
+                    && a.parameters
+                        .iter()
+                        .zip(&b.parameters)
+                        .all(|(a, b)| same(&Ast::Parameter(a.clone()), &Ast::Parameter(b.clone())))
+                    && same(&a.return_type, &b.return_type)
+                    && a.calling_convention == b.calling_convention
+                    && match (&a.body, &b.body) {
+                        (AstProcedureBody::Scope(a), AstProcedureBody::Scope(b)) => {
+                            same_list(&a.expressions, &b.expressions)
+                        }
+                        (AstProcedureBody::ExternName(a), AstProcedureBody::ExternName(b)) => {
+                            a == b
+                        }
+                        _ => false,
+                    }
+            }
+            (Ast::ProcedureType(a), Ast::ProcedureType(b)) => {
+                same_list(&a.parameter_types, &b.parameter_types)
+                    && same(&a.return_type, &b.return_type)
+                    && a.calling_convention == b.calling_convention
+            }
+            (Ast::Parameter(a), Ast::Parameter(b)) => {
+                a.mutable == b.mutable && a.name == b.name && same(&a.typ, &b.typ)
+            }
+            (Ast::Scope(a), Ast::Scope(b)) => same_list(&a.expressions, &b.expressions),
+            (Ast::LetDeclaration(a), Ast::LetDeclaration(b)) => {
+                a.public == b.public
+                    && a.name == b.name
+                    && same_option(&a.typ, &b.typ)
+                    && same(&a.value, &b.value)
+            }
+            (Ast::VarDeclaration(a), Ast::VarDeclaration(b)) => {
+                a.public == b.public
+                    && a.name == b.name
+                    && same_option(&a.typ, &b.typ)
+                    && same(&a.value, &b.value)
+            }
+            (Ast::Name(a), Ast::Name(b)) => a.name == b.name,
+            (Ast::MemberAccess(a), Ast::MemberAccess(b)) => {
+                a.name == b.name && same(&a.operand, &b.operand)
+            }
+            (Ast::Integer(a), Ast::Integer(b)) => {
+                a.value == b.value && a.suffix == b.suffix && a.character == b.character
+            }
+            (Ast::String(a), Ast::String(b)) => a.value == b.value,
+            (Ast::Call(a), Ast::Call(b)) => {
+                same(&a.operand, &b.operand) && same_list(&a.arguments, &b.arguments)
+            }
+            (Ast::Return(a), Ast::Return(b)) => same_option(&a.value, &b.value),
+            (Ast::Unary(a), Ast::Unary(b)) => {
+                a.operator == b.operator && same(&a.operand, &b.operand)
+            }
+            (Ast::Binary(a), Ast::Binary(b)) => {
+                a.operator == b.operator && same(&a.left, &b.left) && same(&a.right, &b.right)
+            }
+            (Ast::If(a), Ast::If(b)) => {
+                same(&a.condition, &b.condition)
+                    && same(&a.then_expression, &b.then_expression)
+                    && same_option(&a.else_expression, &b.else_expression)
+            }
+            (Ast::While(a), Ast::While(b)) => {
+                same(&a.condition, &b.condition) && same(&a.then_expression, &b.then_expression)
+            }
+            (Ast::Cast(a), Ast::Cast(b)) => same(&a.typ, &b.typ) && same(&a.operand, &b.operand),
+            (Ast::Assign(a), Ast::Assign(b)) => {
+                a.direction == b.direction
+                    && same(&a.operand, &b.operand)
+                    && same(&a.value, &b.value)
+            }
+            (Ast::Builtin(a), Ast::Builtin(b)) => a.kind == b.kind,
+            (Ast::StructType(a), Ast::StructType(b)) => same_fields(&a.fields, &b.fields),
+            (Ast::StructLiteral(a), Ast::StructLiteral(b)) => {
+                same(&a.typ, &b.typ) && same_fields(&a.fields, &b.fields)
+            }
+            (Ast::ArrayType(a), Ast::ArrayType(b)) => {
+                same(&a.length, &b.length) && same(&a.element_type, &b.element_type)
+            }
+            (Ast::ArrayLiteral(a), Ast::ArrayLiteral(b)) => same_list(&a.elements, &b.elements),
+            (Ast::Index(a), Ast::Index(b)) => {
+                same(&a.operand, &b.operand) && same(&a.index, &b.index)
+            }
+            (Ast::SliceType(a), Ast::SliceType(b)) => same(&a.element_type, &b.element_type),
+            (Ast::Slice(a), Ast::Slice(b)) => {
+                same(&a.operand, &b.operand) && same(&a.start, &b.start) && same(&a.end, &b.end)
+            }
+            _ => false,
+        }
+    }
+
+    /// Generates random but syntactically valid programs from a fixed seed.
+    struct Generator {
+        state: u64,
+    }
+
+    impl Generator {
+        fn below(&mut self, n: u64) -> u64 {
+            // xorshift64
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state % n
+        }
+
+        fn pick<'a>(&mut self, options: &[&'a str]) -> &'a str {
+            options[self.below(options.len() as u64) as usize]
+        }
+
+        fn typ(&mut self, depth: usize) -> String {
+            match if depth == 0 { 0 } else { self.below(6) } {
+                0 => self
+                    .pick(&["u8", "s64", "bool", "void", "type", "string"])
+                    .into(),
+                1 => format!("^{}", self.typ(depth - 1)),
+                5 => match self.below(2) {
+                    0 => format!("[{}]{}", self.below(10), self.typ(depth - 1)),
+                    _ => format!("[]{}", self.typ(depth - 1)),
+                },
+                2 => format!("({})", self.typ(depth - 1)),
+                3 => format!(
+                    "struct {{ x: {}, y: {} }}",
+                    self.typ(depth - 1),
+                    self.typ(depth - 1)
+                ),
+                _ => format!(
+                    "proc({}, {}) => ({}) {}",
+                    self.typ(depth - 1),
+                    self.typ(depth - 1),
+                    self.typ(depth - 1),
+                    self.pick(&["", "#stdcall", "#fastcall", "#cdecl"])
+                ),
+            }
+        }
+
+        fn expression(&mut self, depth: usize) -> String {
+            match if depth == 0 {
+                self.below(2)
+            } else {
+                self.below(16)
+            } {
+                0 => self
+                    .pick(&[
+                        "a",
+                        "b",
+                        "value",
+                        "ptr",
+                        "\"text\"",
+                        "\"\\\"\\n\\u{1F600}\"",
+                    ])
+                    .into(),
+                1 => match self.below(4) {
+                    0 => self
+                        .pick(&["'a'", "'\\n'", "'\\''", "'\\u{E9}'u16", "'0's64"])
+                        .into(),
+                    _ => format!("{}{}", self.below(300), self.pick(&["", "", "u8", "s64"])),
+                },
+                2 => format!(
+                    "{}{}",
+                    self.pick(&["-", "+", "&", "^"]),
+                    self.expression(depth - 1)
+                ),
+                3 => format!("{}^", self.expression(depth - 1)),
+                4 | 5 => format!(
+                    "{} {} {}",
+                    self.expression(depth - 1),
+                    self.pick(&["+", "-", "*", "/", "%", "==", "!=", "<", ">", "<=", ">="]),
+                    self.expression(depth - 1)
+                ),
+                6 => format!("({})", self.expression(depth - 1)),
+                7 => format!(
+                    "{}({}, {})",
+                    self.expression(depth - 1),
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+                8 => format!("{}.member", self.expression(depth - 1)),
+                9 => format!(
+                    "{}.{{ x = {}, y = {} }}",
+                    self.pick(&["Point", "struct { x: u8, y: u8 }"]),
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+                10 => format!("cast({}) {}", self.typ(1), self.expression(depth - 1)),
+                11 => format!(
+                    "({} {} {})",
+                    self.expression(depth - 1),
+                    self.pick(&["<-", "->"]),
+                    self.expression(depth - 1)
+                ),
+                12 => format!(
+                    ".[{}, {}]",
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+                13 => format!(
+                    "{}[{}]",
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+                14 => format!(
+                    "{}[{}..{}]",
+                    self.expression(depth - 1),
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+                _ => format!(
+                    "(if {} {{ {} }})",
+                    self.expression(depth - 1),
+                    self.expression(depth - 1)
+                ),
+            }
+        }
+
+        fn statement(&mut self, depth: usize, indent: usize) -> String {
+            match if depth == 0 { 0 } else { self.below(9) } {
+                0 | 1 => self.expression(3),
+                2 => format!(
+                    "let a{} = {}",
+                    if self.below(2) == 0 {
+                        format!(": {}", self.typ(2))
+                    } else {
+                        "".into()
+                    },
+                    self.expression(3)
+                ),
+                3 => format!("var b <- {}", self.expression(3)),
+                4 => format!("{} <- {}", self.expression(2), self.expression(3)),
+                5 => format!("return {}", self.expression(2)),
+                6 => format!(
+                    "if {} {} else {}",
+                    self.expression(2),
+                    self.scope(depth - 1, indent),
+                    self.scope(depth - 1, indent)
+                ),
+                7 => format!(
+                    "while {} {}",
+                    self.expression(2),
+                    self.scope(depth - 1, indent)
+                ),
+                _ => format!(
+                    "proc f(var value: {}, ptr: {}) => {} {}",
+                    self.typ(2),
+                    self.typ(2),
+                    self.typ(1),
+                    self.scope(depth - 1, indent)
+                ),
+            }
+        }
+
+        fn scope(&mut self, depth: usize, indent: usize) -> String {
+            let mut scope = "{\n".to_string();
+            for _ in 0..self.below(4) {
+                scope.push_str(&"    ".repeat(indent + 1));
+                scope.push_str(&self.statement(depth, indent + 1));
+                scope.push('\n');
+            }
+            scope.push_str(&"    ".repeat(indent));
+            scope.push('}');
+            scope
+        }
+    }
+
+    #[test]
+    fn print_declarations() {
+        let source = "\
+#import \"lib.lang\" as lib
+pub proc f(var value: ^u8, ptr: proc(u8) => bool #stdcall) => ^u8 {
+    let x: ^u8 = -value^ + 2 * lib.a
+    cast(u8) x -> ptr(x)^
+}
+proc g() => void #extern \"g\"
+";
+        assert_eq!(
+            parse(source).to_string(),
+            "\
+#import \"lib.lang\" as lib
+pub proc f(var value: (^u8), ptr: (proc(u8) => bool #stdcall)) => (^u8) {
+    let x: (^u8) = (-(value^)) + (2 * lib.a)
+    (cast(u8) x) -> (ptr(x)^)
+}
+proc g() => void #extern \"g\"
+"
+        );
+    }
+
+    #[test]
+    fn print_roundtrip() {
+        for seed in 1..300u64 {
+            let mut generator = Generator {
+                state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15),
+            };
+            let mut source = String::new();
+            for _ in 0..4 {
+                source.push_str(&generator.statement(3, 0));
+                source.push('\n');
+            }
+            let ast = parse(&source);
+            let printed = ast.to_string();
+            assert!(
+                same(&parse(&printed), &ast),
+                "{source}\nprinted as\n{printed}"
+            );
+        }
+    }
+}
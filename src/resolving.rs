@@ -4,10 +4,12 @@ use derive_more::{Display, IsVariant};
 use enum_as_inner::EnumAsInner;
 
 use crate::{
-    eval, get_or_add_type_bool, get_or_add_type_integer, get_or_add_type_pointer,
-    get_or_add_type_procedure, get_or_add_type_type, get_or_add_type_void, Ast, AstAssignDirection,
-    AstBuiltin, AstBuiltinKind, AstLet, AstParameter, AstProcedure, AstProcedureBody, AstVar,
-    BinaryOperator, SourceSpan, Type, UnaryOperator,
+    eval, get_or_add_type_array, get_or_add_type_bool, get_or_add_type_integer,
+    get_or_add_type_pointer, get_or_add_type_procedure, get_or_add_type_slice,
+    get_or_add_type_type, get_or_add_type_void, Ast, AstAssignDirection, AstBuiltin,
+    AstBuiltinKind, AstFile, AstImport, AstImportKind, AstInteger, AstLet, AstParameter,
+    AstProcedure, AstProcedureBody, AstStructField, AstVar, BinaryOperator, Diagnostic, SourceSpan,
+    Type, UnaryOperator,
 };
 
 #[derive(Clone, Debug, Display, PartialEq, IsVariant, EnumAsInner)]
@@ -34,6 +36,225 @@ pub enum ResolvingError {
     ProcedureNoReturn { procedure: Rc<AstProcedure> },
     #[display(fmt = "{location}: Operand is not assignable")]
     NotAssignable { location: SourceSpan },
+    #[display(fmt = "{location}: Cannot take the address of this operand")]
+    NotAddressable { location: SourceSpan },
+    #[display(fmt = "{location}: Cyclic dependency found while resolving this expression")]
+    CyclicDependency { location: SourceSpan },
+    #[display(fmt = "{location}: Expression cannot be evaluated at compile time")]
+    NotConstant { location: SourceSpan },
+    #[display(fmt = "{location}: Cannot call a value of type '{typ}'")]
+    NotCallable { typ: Rc<Type>, location: SourceSpan },
+    #[display(fmt = "{location}: Expected {expected} arguments, but got {got}")]
+    WrongArgumentCount {
+        expected: usize,
+        got: usize,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: '{filepath}' has no member '{name}'")]
+    UnknownModuleMember {
+        name: String,
+        filepath: String,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: '{name}' is private to {declared}")]
+    PrivateDeclaration {
+        name: String,
+        location: SourceSpan,
+        declared: SourceSpan,
+    },
+    #[display(fmt = "{location}: A module can only be used to access its members")]
+    ModuleUsedAsValue { location: SourceSpan },
+    #[display(fmt = "{location}: You can only use return inside a procedure")]
+    ReturnOutsideProcedure { location: SourceSpan },
+    #[display(fmt = "{location}: Expected an integer type, but got type '{got}'")]
+    ExpectedIntegerType { got: Rc<Type>, location: SourceSpan },
+    #[display(fmt = "{location}: Expected a pointer type, but got type '{got}'")]
+    ExpectedPointerType { got: Rc<Type>, location: SourceSpan },
+    #[display(
+        fmt = "{}: Integer literal '{}{}' does not fit in type '{}'",
+        location,
+        "if *negated { \"-\" } else { \"\" }",
+        value,
+        typ
+    )]
+    IntegerDoesNotFit {
+        value: u128,
+        negated: bool,
+        typ: Rc<Type>,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: Cannot cast from type '{from}' to type '{to}'")]
+    InvalidCast {
+        from: Rc<Type>,
+        to: Rc<Type>,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: Expected a struct type, but got type '{got}'")]
+    ExpectedStructType { got: Rc<Type>, location: SourceSpan },
+    #[display(fmt = "{location}: Type '{typ}' has no field '{name}'")]
+    UnknownField {
+        name: String,
+        typ: Rc<Type>,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: Missing field '{name}' of type '{typ}'")]
+    MissingField {
+        name: String,
+        typ: Rc<Type>,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{new}: Duplicate field '{name}', the original field was here: {old}")]
+    DuplicateField {
+        name: String,
+        new: SourceSpan,
+        old: SourceSpan,
+    },
+    #[display(fmt = "{location}: Cannot compare values of type '{typ}'")]
+    NotComparable { typ: Rc<Type>, location: SourceSpan },
+    #[display(fmt = "{location}: Cannot index a value of type '{typ}'")]
+    NotIndexable { typ: Rc<Type>, location: SourceSpan },
+    #[display(fmt = "{location}: Index {index} is out of bounds for an array of length {length}")]
+    IndexOutOfBounds {
+        index: u128,
+        length: usize,
+        location: SourceSpan,
+    },
+    #[display(fmt = "{location}: Cannot infer the element type of an empty array literal")]
+    UntypedEmptyArray { location: SourceSpan },
+    #[display(fmt = "{location}: Array length {length} is too large")]
+    ArrayTooLong { length: i128, location: SourceSpan },
+}
+
+impl ResolvingError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            ResolvingError::Redeclaration { name, new, old } => {
+                Diagnostic::error(*new, format!("Redeclaration of '{name}'"))
+                    .with_label(*old, "original declaration")
+            }
+            ResolvingError::UndeclaredName { name, location } => {
+                Diagnostic::error(*location, format!("'{name}' is undeclared"))
+            }
+            ResolvingError::ExpectedType {
+                expected,
+                got,
+                location,
+            } => Diagnostic::error(
+                *location,
+                format!("Expected type '{expected}', but got type '{got}'"),
+            ),
+            ResolvingError::ProcedureNoReturn { procedure } => Diagnostic::error(
+                procedure.location,
+                format!("'{}' does not return in all control paths", procedure.name),
+            ),
+            ResolvingError::NotAssignable { location } => {
+                Diagnostic::error(*location, "Operand is not assignable")
+            }
+            ResolvingError::NotAddressable { location } => {
+                Diagnostic::error(*location, "Cannot take the address of this operand")
+            }
+            ResolvingError::CyclicDependency { location } => Diagnostic::error(
+                *location,
+                "Cyclic dependency found while resolving this expression",
+            ),
+            ResolvingError::NotConstant { location } => {
+                Diagnostic::error(*location, "Expression cannot be evaluated at compile time")
+            }
+            ResolvingError::NotCallable { typ, location } => {
+                Diagnostic::error(*location, format!("Cannot call a value of type '{typ}'"))
+            }
+            ResolvingError::WrongArgumentCount {
+                expected,
+                got,
+                location,
+            } => Diagnostic::error(
+                *location,
+                format!("Expected {expected} arguments, but got {got}"),
+            ),
+            ResolvingError::UnknownModuleMember {
+                name,
+                filepath,
+                location,
+            } => Diagnostic::error(*location, format!("'{filepath}' has no member '{name}'")),
+            ResolvingError::PrivateDeclaration {
+                name,
+                location,
+                declared,
+            } => Diagnostic::error(*location, format!("'{name}' is private"))
+                .with_label(*declared, "declared here without 'pub'")
+                .with_note("only 'pub' declarations can be used from other files"),
+            ResolvingError::ModuleUsedAsValue { location } => {
+                Diagnostic::error(*location, "A module can only be used to access its members")
+            }
+            ResolvingError::ReturnOutsideProcedure { location } => {
+                Diagnostic::error(*location, "You can only use return inside a procedure")
+            }
+            ResolvingError::ExpectedIntegerType { got, location } => Diagnostic::error(
+                *location,
+                format!("Expected an integer type, but got type '{got}'"),
+            ),
+            ResolvingError::ExpectedPointerType { got, location } => Diagnostic::error(
+                *location,
+                format!("Expected a pointer type, but got type '{got}'"),
+            ),
+            ResolvingError::IntegerDoesNotFit {
+                value,
+                negated,
+                typ,
+                location,
+            } => Diagnostic::error(
+                *location,
+                format!(
+                    "Integer literal '{}{value}' does not fit in type '{typ}'",
+                    if *negated { "-" } else { "" }
+                ),
+            ),
+            ResolvingError::InvalidCast { from, to, location } => Diagnostic::error(
+                *location,
+                format!("Cannot cast from type '{from}' to type '{to}'"),
+            ),
+            ResolvingError::ExpectedStructType { got, location } => Diagnostic::error(
+                *location,
+                format!("Expected a struct type, but got type '{got}'"),
+            ),
+            ResolvingError::UnknownField {
+                name,
+                typ,
+                location,
+            } => Diagnostic::error(*location, format!("Type '{typ}' has no field '{name}'")),
+            ResolvingError::MissingField {
+                name,
+                typ,
+                location,
+            } => Diagnostic::error(*location, format!("Missing field '{name}' of type '{typ}'")),
+            ResolvingError::DuplicateField { name, new, old } => {
+                Diagnostic::error(*new, format!("Duplicate field '{name}'"))
+                    .with_label(*old, "original field")
+            }
+            ResolvingError::NotComparable { typ, location } => {
+                Diagnostic::error(*location, format!("Cannot compare values of type '{typ}'"))
+            }
+            ResolvingError::NotIndexable { typ, location } => {
+                Diagnostic::error(*location, format!("Cannot index a value of type '{typ}'"))
+            }
+            ResolvingError::IndexOutOfBounds {
+                index,
+                length,
+                location,
+            } => Diagnostic::error(
+                *location,
+                format!("Index {index} is out of bounds for an array of length {length}"),
+            ),
+            ResolvingError::UntypedEmptyArray { location } => Diagnostic::error(
+                *location,
+                "Cannot infer the element type of an empty array literal",
+            )
+            .with_note("give the declaration a type like '[0]u8'"),
+            ResolvingError::ArrayTooLong { length, location } => {
+                Diagnostic::error(*location, format!("Array length {length} is too large"))
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, IsVariant, EnumAsInner)]
@@ -43,11 +264,54 @@ pub enum Declaration {
     Let(Rc<AstLet>),
     Var(Rc<AstVar>),
     Builtin(Rc<AstBuiltin>),
+    /// A file imported with `#import "x.lang" as x`, inside procedures only the
+    /// procedures of the module can be used.
+    Module {
+        import: Rc<AstImport>,
+        only_procedures: bool,
+    },
+    /// A private declaration of a file imported with a plain `#import`, it never hides
+    /// another name and is only in scope to report that it is private when used.
+    PrivateImport(Box<Declaration>),
 }
 
 impl Declaration {
     pub fn is_visible_through_procedures(&self) -> bool {
-        matches!(self, Declaration::Procedure(_) | Declaration::Builtin(_))
+        match self {
+            Declaration::Procedure(_) | Declaration::Builtin(_) | Declaration::Module { .. } => {
+                true
+            }
+            // constants like named types have no storage a procedure could not reach
+            Declaration::Let(declaration) => is_constant(&declaration.value),
+            Declaration::Parameter(_) | Declaration::Var(_) => false,
+            Declaration::PrivateImport(declaration) => declaration.is_visible_through_procedures(),
+        }
+    }
+
+    /// Whether other files can use the declaration, only top level declarations marked
+    /// with `pub` can.
+    pub fn is_public(&self) -> bool {
+        match self {
+            Declaration::Procedure(procedure) => procedure.public,
+            Declaration::Let(declaration) => declaration.public,
+            Declaration::Var(declaration) => declaration.public,
+            Declaration::Parameter(_) | Declaration::Builtin(_) | Declaration::Module { .. } => {
+                true
+            }
+            Declaration::PrivateImport(_) => false,
+        }
+    }
+
+    /// The declaration as it is seen from inside a procedure.
+    fn through_procedure(&self) -> Option<Declaration> {
+        match self {
+            Declaration::Module { import, .. } => Some(Declaration::Module {
+                import: import.clone(),
+                only_procedures: true,
+            }),
+            declaration if declaration.is_visible_through_procedures() => Some(declaration.clone()),
+            _ => None,
+        }
     }
 
     pub fn to_ast(&self) -> Ast {
@@ -57,6 +321,8 @@ impl Declaration {
             Declaration::Let(declaration) => Ast::LetDeclaration(declaration.clone()),
             Declaration::Var(declaration) => Ast::VarDeclaration(declaration.clone()),
             Declaration::Builtin(builtin) => Ast::Builtin(builtin.clone()),
+            Declaration::Module { import, .. } => Ast::Import(import.clone()),
+            Declaration::PrivateImport(declaration) => declaration.to_ast(),
         }
     }
 }
@@ -64,6 +330,7 @@ impl Declaration {
 fn is_assignable(ast: &Ast) -> bool {
     match ast {
         Ast::File(_) => false,
+        Ast::Import(_) => false,
         Ast::Procedure(_) => false,
         Ast::ProcedureType(_) => false,
         Ast::Parameter(parameter) => parameter.mutable,
@@ -71,7 +338,23 @@ fn is_assignable(ast: &Ast) -> bool {
         Ast::LetDeclaration(_) => false,
         Ast::VarDeclaration(_) => true,
         Ast::Name(name) => is_assignable(name.resolved_declaration.borrow().as_ref().unwrap()),
+        Ast::MemberAccess(member_access) => match &*member_access.resolved_declaration.borrow() {
+            Some(declaration) => is_assignable(declaration),
+            // a field can be assigned when its struct can, or always through a pointer, the
+            // length of a slice or array never can
+            None => {
+                let operand_type = member_access.operand.get_type().unwrap();
+                operand_type
+                    .as_pointer()
+                    .unwrap_or(&operand_type)
+                    .as_struct()
+                    .is_some()
+                    && (operand_type.as_pointer().is_some()
+                        || is_assignable(&member_access.operand))
+            }
+        },
         Ast::Integer(_) => false,
+        Ast::String(_) => false,
         Ast::Call(_) => false,
         Ast::Return(_) => false,
         Ast::Unary(unary) => match &unary.operator {
@@ -88,12 +371,27 @@ fn is_assignable(ast: &Ast) -> bool {
         Ast::Cast(_) => false,
         Ast::Assign(_) => false,
         Ast::Builtin(_) => false,
+        Ast::StructType(_) => false,
+        Ast::StructLiteral(_) => false,
+        Ast::ArrayType(_) => false,
+        Ast::ArrayLiteral(_) => false,
+        // like fields, elements are assignable when their array is or through a pointer, the
+        // elements of a slice always are
+        Ast::Index(index) => {
+            let operand_type = index.operand.get_type().unwrap();
+            operand_type.as_pointer().is_some()
+                || operand_type.as_slice().is_some()
+                || is_assignable(&index.operand)
+        }
+        Ast::SliceType(_) => false,
+        Ast::Slice(_) => false,
     }
 }
 
 fn is_addressable(ast: &Ast) -> bool {
     match ast {
         Ast::File(_) => false,
+        Ast::Import(_) => false,
         Ast::Procedure(_) => false,
         Ast::ProcedureType(_) => true,
         Ast::Parameter(parameter) => parameter.mutable,
@@ -101,7 +399,22 @@ fn is_addressable(ast: &Ast) -> bool {
         Ast::LetDeclaration(_) => false,
         Ast::VarDeclaration(_) => true,
         Ast::Name(name) => is_addressable(name.resolved_declaration.borrow().as_ref().unwrap()),
+        Ast::MemberAccess(member_access) => match &*member_access.resolved_declaration.borrow() {
+            Some(declaration) => is_addressable(declaration),
+            // the length of a slice or array is a temporary
+            None => {
+                let operand_type = member_access.operand.get_type().unwrap();
+                operand_type
+                    .as_pointer()
+                    .unwrap_or(&operand_type)
+                    .as_struct()
+                    .is_none()
+                    || operand_type.as_pointer().is_some()
+                    || is_addressable(&member_access.operand)
+            }
+        },
         Ast::Integer(_) => true,
+        Ast::String(_) => true,
         Ast::Call(_) => true,
         Ast::Return(_) => false,
         Ast::Unary(_) => true,
@@ -111,9 +424,91 @@ fn is_addressable(ast: &Ast) -> bool {
         Ast::Cast(_) => true,
         Ast::Assign(_) => true,
         Ast::Builtin(_) => false,
+        Ast::StructType(_) => true,
+        Ast::StructLiteral(_) => true,
+        Ast::ArrayType(_) => true,
+        Ast::ArrayLiteral(_) => true,
+        Ast::Index(index) => {
+            let operand_type = index.operand.get_type().unwrap();
+            operand_type.as_pointer().is_some()
+                || operand_type.as_slice().is_some()
+                || is_addressable(&index.operand)
+        }
+        Ast::SliceType(_) => true,
+        Ast::Slice(_) => true,
     }
 }
 
+pub(crate) fn is_constant(ast: &Ast) -> bool {
+    match ast {
+        Ast::File(_) => false,
+        Ast::Import(_) => false,
+        Ast::Procedure(_) => true,
+        Ast::ProcedureType(procedure_type) => {
+            procedure_type.parameter_types.iter().all(is_constant)
+                && is_constant(&procedure_type.return_type)
+        }
+        Ast::Parameter(_) => false,
+        Ast::Scope(_) => false,
+        Ast::LetDeclaration(declaration) => is_constant(&declaration.value),
+        Ast::VarDeclaration(_) => false,
+        Ast::Name(name) => is_constant(name.resolved_declaration.borrow().as_ref().unwrap()),
+        Ast::MemberAccess(member_access) => member_access
+            .resolved_declaration
+            .borrow()
+            .as_ref()
+            .is_some_and(is_constant),
+        Ast::Integer(_) => true,
+        Ast::String(_) => false,
+        Ast::Call(_) => false,
+        Ast::Return(_) => false,
+        Ast::Unary(unary) => match &unary.operator {
+            UnaryOperator::Identity => is_constant(&unary.operand),
+            UnaryOperator::Negation => false,
+            UnaryOperator::LogicalNot => is_constant(&unary.operand),
+            UnaryOperator::PointerType => is_constant(&unary.operand),
+            UnaryOperator::AddressOf => false,
+            UnaryOperator::Dereference => false,
+        },
+        Ast::Binary(binary) => match &binary.operator {
+            BinaryOperator::Equal | BinaryOperator::NotEqual => {
+                is_constant(&binary.left) && is_constant(&binary.right)
+            }
+            _ => false,
+        },
+        Ast::If(iff) => {
+            is_constant(&iff.condition)
+                && is_constant(&iff.then_expression)
+                && iff.else_expression.as_ref().is_none_or(is_constant)
+        }
+        Ast::While(_) => false,
+        Ast::Cast(_) => false,
+        Ast::Assign(_) => false,
+        Ast::Builtin(_) => true,
+        Ast::StructType(struct_type) => struct_type
+            .fields
+            .iter()
+            .all(|field| is_constant(&field.value)),
+        Ast::StructLiteral(_) => false,
+        Ast::ArrayType(array_type) => {
+            is_constant(&array_type.length) && is_constant(&array_type.element_type)
+        }
+        Ast::ArrayLiteral(_) => false,
+        Ast::Index(_) => false,
+        Ast::SliceType(slice_type) => is_constant(&slice_type.element_type),
+        Ast::Slice(_) => false,
+    }
+}
+
+fn eval_type(ast: &Ast, type_cache: &mut Vec<Rc<Type>>) -> Result<Rc<Type>, ResolvingError> {
+    if !is_constant(ast) {
+        return Err(ResolvingError::NotConstant {
+            location: ast.get_location(),
+        });
+    }
+    Ok(eval(ast, type_cache).as_type().unwrap().clone())
+}
+
 pub fn resolve_names(
     ast: &Ast,
     names: &mut HashMap<String, Declaration>,
@@ -123,56 +518,44 @@ pub fn resolve_names(
         names: &mut HashMap<String, Declaration>,
     ) -> Result<(), ResolvingError> {
         for expression in expressions {
-            match expression {
-                Ast::Procedure(procedure) => {
-                    if let Some(old) = names.insert(
-                        procedure.name.clone(),
-                        Declaration::Procedure(procedure.clone()),
-                    ) {
-                        return Err(ResolvingError::Redeclaration {
-                            name: procedure.name.clone(),
-                            new: procedure.location.clone(),
-                            old: old.to_ast().get_location(),
-                        });
-                    }
-                }
-                _ => (),
+            if let Ast::Procedure(procedure) = expression {
+                declare(
+                    names,
+                    &procedure.name,
+                    Declaration::Procedure(procedure.clone()),
+                    procedure.location,
+                )?;
             }
         }
         for expression in expressions {
             resolve_names(expression, &mut names.clone())?;
             match expression {
                 Ast::File(_) => (),
+                Ast::Import(_) => (),    // imports are declared by the file
                 Ast::Procedure(_) => (), // procedures are already declared
                 Ast::ProcedureType(_) => (),
                 Ast::Parameter(_) => (),
                 Ast::Scope(_) => (),
                 Ast::LetDeclaration(declaration) => {
-                    if let Some(old) = names.insert(
-                        declaration.name.clone(),
+                    declare(
+                        names,
+                        &declaration.name,
                         Declaration::Let(declaration.clone()),
-                    ) {
-                        return Err(ResolvingError::Redeclaration {
-                            name: declaration.name.clone(),
-                            new: declaration.location.clone(),
-                            old: old.to_ast().get_location(),
-                        });
-                    }
+                        declaration.location,
+                    )?;
                 }
                 Ast::VarDeclaration(declaration) => {
-                    if let Some(old) = names.insert(
-                        declaration.name.clone(),
+                    declare(
+                        names,
+                        &declaration.name,
                         Declaration::Var(declaration.clone()),
-                    ) {
-                        return Err(ResolvingError::Redeclaration {
-                            name: declaration.name.clone(),
-                            new: declaration.location.clone(),
-                            old: old.to_ast().get_location(),
-                        });
-                    }
+                        declaration.location,
+                    )?;
                 }
                 Ast::Name(_) => (),
+                Ast::MemberAccess(_) => (),
                 Ast::Integer(_) => (),
+                Ast::String(_) => (),
                 Ast::Call(_) => (),
                 Ast::Return(_) => (),
                 Ast::Unary(_) => (),
@@ -182,32 +565,45 @@ pub fn resolve_names(
                 Ast::Cast(_) => (),
                 Ast::Assign(_) => (),
                 Ast::Builtin(_) => (),
+                Ast::StructType(_) => (),
+                Ast::StructLiteral(_) => (),
+                Ast::ArrayType(_) => (),
+                Ast::ArrayLiteral(_) => (),
+                Ast::Index(_) => (),
+                Ast::SliceType(_) => (),
+                Ast::Slice(_) => (),
             }
         }
         Ok(())
     }
 
-    Ok(match ast {
+    match ast {
         Ast::File(file) => {
-            scope_like(&file.expressions, &mut names.clone())?;
+            if !file.names_resolved.replace(true) {
+                let mut file_names = names.clone();
+                for expression in &file.expressions {
+                    if let Ast::Import(import) = expression {
+                        // imported files start from the same names as the root file
+                        resolve_names(&Ast::File(import.file.clone()), &mut names.clone())?;
+                        declare_import(import, &mut file_names)?;
+                    }
+                }
+                scope_like(&file.expressions, &mut file_names)?;
+            }
         }
+        Ast::Import(_) => (), // declared by the file containing the import
         Ast::Procedure(procedure) => {
             if !names.contains_key(&procedure.name) {
-                if let Some(old) = names.insert(
-                    procedure.name.clone(),
+                declare(
+                    names,
+                    &procedure.name,
                     Declaration::Procedure(procedure.clone()),
-                ) {
-                    return Err(ResolvingError::Redeclaration {
-                        name: procedure.name.clone(),
-                        new: procedure.location.clone(),
-                        old: old.to_ast().get_location(),
-                    });
-                }
+                    procedure.location,
+                )?;
             }
             let mut names = names
                 .iter()
-                .filter(|(_, decl)| decl.is_visible_through_procedures())
-                .map(|(name, decl)| (name.clone(), decl.clone()))
+                .filter_map(|(name, decl)| Some((name.clone(), decl.through_procedure()?)))
                 .collect::<HashMap<String, Declaration>>();
             for parameter in &procedure.parameters {
                 resolve_names(&Ast::Parameter(parameter.clone()), &mut names)?;
@@ -228,16 +624,12 @@ pub fn resolve_names(
         }
         Ast::Parameter(parameter) => {
             resolve_names(&parameter.typ, names)?;
-            if let Some(old) = names.insert(
-                parameter.name.clone(),
+            declare(
+                names,
+                &parameter.name,
                 Declaration::Parameter(parameter.clone()),
-            ) {
-                return Err(ResolvingError::Redeclaration {
-                    name: parameter.name.clone(),
-                    new: parameter.location.clone(),
-                    old: old.to_ast().get_location(),
-                });
-            }
+                parameter.location,
+            )?;
         }
         Ast::Scope(scope) => {
             scope_like(&scope.expressions, &mut names.clone())?;
@@ -247,32 +639,24 @@ pub fn resolve_names(
                 resolve_names(typ, names)?;
             }
             resolve_names(&declaration.value, names)?;
-            if let Some(old) = names.insert(
-                declaration.name.clone(),
+            declare(
+                names,
+                &declaration.name,
                 Declaration::Let(declaration.clone()),
-            ) {
-                return Err(ResolvingError::Redeclaration {
-                    name: declaration.name.clone(),
-                    new: declaration.location.clone(),
-                    old: old.to_ast().get_location(),
-                });
-            }
+                declaration.location,
+            )?;
         }
         Ast::VarDeclaration(declaration) => {
             if let Some(typ) = &declaration.typ {
                 resolve_names(typ, names)?;
             }
             resolve_names(&declaration.value, names)?;
-            if let Some(old) = names.insert(
-                declaration.name.clone(),
+            declare(
+                names,
+                &declaration.name,
                 Declaration::Var(declaration.clone()),
-            ) {
-                return Err(ResolvingError::Redeclaration {
-                    name: declaration.name.clone(),
-                    new: declaration.location.clone(),
-                    old: old.to_ast().get_location(),
-                });
-            }
+                declaration.location,
+            )?;
         }
         Ast::Name(name) => {
             if name.resolved_declaration.borrow().is_none() {
@@ -281,13 +665,58 @@ pub fn resolve_names(
                 } else {
                     return Err(ResolvingError::UndeclaredName {
                         name: name.name.clone(),
-                        location: name.location.clone(),
+                        location: name.location,
                     });
                 };
+                if decl.is_module() {
+                    return Err(ResolvingError::ModuleUsedAsValue {
+                        location: name.location,
+                    });
+                }
+                if let Declaration::PrivateImport(decl) = decl {
+                    check_public(decl, &name.name, &name.location)?;
+                }
                 *name.resolved_declaration.borrow_mut() = Some(decl.to_ast());
             }
         }
+        Ast::MemberAccess(member_access) => {
+            if member_access.resolved_declaration.borrow().is_none() {
+                // a module operand is not resolved as a name, as that would reject the module
+                let module = match &member_access.operand {
+                    Ast::Name(name) => match names.get(&name.name) {
+                        Some(Declaration::Module {
+                            import,
+                            only_procedures,
+                        }) => Some((import.clone(), *only_procedures)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some((import, only_procedures)) = module {
+                    let operand_name = member_access.operand.as_name().unwrap();
+                    *operand_name.resolved_declaration.borrow_mut() =
+                        Some(Ast::Import(import.clone()));
+                    let member = file_declarations(&import.file)
+                        .find(|(name, decl)| {
+                            name.as_str() == member_access.name
+                                && (!only_procedures || decl.is_visible_through_procedures())
+                        })
+                        .map(|(_, decl)| decl)
+                        .ok_or_else(|| ResolvingError::UnknownModuleMember {
+                            name: member_access.name.clone(),
+                            filepath: import.path.clone(),
+                            location: member_access.location,
+                        })?;
+                    check_public(&member, &member_access.name, &member_access.location)?;
+                    *member_access.resolved_declaration.borrow_mut() = Some(member.to_ast());
+                } else {
+                    // otherwise it is a field, which is looked up once the operand has a type
+                    resolve_names(&member_access.operand, names)?;
+                }
+            }
+        }
         Ast::Integer(_) => (),
+        Ast::String(_) => (),
         Ast::Call(call) => {
             resolve_names(&call.operand, names)?;
             for argument in &call.arguments {
@@ -336,8 +765,138 @@ pub fn resolve_names(
             AstBuiltinKind::Void => (),
             AstBuiltinKind::Bool => (),
             AstBuiltinKind::IntegerType { size: _, signed: _ } => (),
+            AstBuiltinKind::String => (),
         },
-    })
+        Ast::StructType(struct_type) => {
+            for field in &struct_type.fields {
+                resolve_names(&field.value, names)?;
+            }
+        }
+        Ast::StructLiteral(literal) => {
+            resolve_names(&literal.typ, names)?;
+            for field in &literal.fields {
+                resolve_names(&field.value, names)?;
+            }
+        }
+        Ast::ArrayType(array_type) => {
+            resolve_names(&array_type.length, names)?;
+            resolve_names(&array_type.element_type, names)?;
+        }
+        Ast::ArrayLiteral(literal) => {
+            for element in &literal.elements {
+                resolve_names(element, names)?;
+            }
+        }
+        Ast::Index(index) => {
+            resolve_names(&index.operand, names)?;
+            resolve_names(&index.index, names)?;
+        }
+        Ast::SliceType(slice_type) => resolve_names(&slice_type.element_type, names)?,
+        Ast::Slice(slice) => {
+            resolve_names(&slice.operand, names)?;
+            resolve_names(&slice.start, names)?;
+            resolve_names(&slice.end, names)?;
+        }
+    }
+    Ok(())
+}
+
+fn declare(
+    names: &mut HashMap<String, Declaration>,
+    name: &str,
+    declaration: Declaration,
+    location: SourceSpan,
+) -> Result<(), ResolvingError> {
+    if let Some(old) = names.insert(name.into(), declaration) {
+        if !old.is_private_import() {
+            return Err(ResolvingError::Redeclaration {
+                name: name.into(),
+                new: location,
+                old: old.to_ast().get_location(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_public(
+    declaration: &Declaration,
+    name: &str,
+    location: &SourceSpan,
+) -> Result<(), ResolvingError> {
+    if !declaration.is_public() {
+        Err(ResolvingError::PrivateDeclaration {
+            name: name.into(),
+            location: *location,
+            declared: declaration.to_ast().get_location(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// The declarations at the top level of a file, which are what importing it provides.
+fn file_declarations(file: &AstFile) -> impl Iterator<Item = (&String, Declaration)> {
+    file.expressions
+        .iter()
+        .filter_map(|expression| match expression {
+            Ast::Procedure(procedure) => {
+                Some((&procedure.name, Declaration::Procedure(procedure.clone())))
+            }
+            Ast::LetDeclaration(declaration) => {
+                Some((&declaration.name, Declaration::Let(declaration.clone())))
+            }
+            Ast::VarDeclaration(declaration) => {
+                Some((&declaration.name, Declaration::Var(declaration.clone())))
+            }
+            _ => None,
+        })
+}
+
+fn declare_import(
+    import: &Rc<AstImport>,
+    names: &mut HashMap<String, Declaration>,
+) -> Result<(), ResolvingError> {
+    let declarations: Vec<(String, Declaration, SourceSpan)> = match &import.kind {
+        AstImportKind::All => file_declarations(&import.file)
+            .map(|(name, decl)| (name.clone(), decl, import.location))
+            .collect(),
+        AstImportKind::Module(name) => vec![(
+            name.clone(),
+            Declaration::Module {
+                import: import.clone(),
+                only_procedures: false,
+            },
+            import.location,
+        )],
+        AstImportKind::Names(imported_names) => imported_names
+            .iter()
+            .map(|(imported_name, location)| {
+                let (name, decl) = file_declarations(&import.file)
+                    .find(|(name, _)| *name == imported_name)
+                    .ok_or_else(|| ResolvingError::UnknownModuleMember {
+                        name: imported_name.clone(),
+                        filepath: import.path.clone(),
+                        location: *location,
+                    })?;
+                check_public(&decl, name, location)?;
+                Ok((name.clone(), decl, *location))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+    for (name, decl, location) in declarations {
+        if !decl.is_public() {
+            names
+                .entry(name)
+                .or_insert_with(|| Declaration::PrivateImport(decl.into()));
+            continue;
+        }
+        // importing the same declaration twice is fine
+        if names.get(&name).map(Declaration::to_ast) != Some(decl.to_ast()) {
+            declare(names, &name, decl, location)?;
+        }
+    }
+    Ok(())
 }
 
 fn expect_type(
@@ -356,6 +915,60 @@ fn expect_type(
     }
 }
 
+fn check_duplicate_field(
+    previous: &[AstStructField],
+    field: &AstStructField,
+) -> Result<(), ResolvingError> {
+    if let Some(original) = previous.iter().find(|original| original.name == field.name) {
+        Err(ResolvingError::DuplicateField {
+            name: field.name.clone(),
+            new: field.location,
+            old: original.location,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn resolve_integer(
+    integer: &AstInteger,
+    negated: bool,
+    suggested_type: Option<Rc<Type>>,
+    type_cache: &mut Vec<Rc<Type>>,
+) -> Result<(), ResolvingError> {
+    let typ = if let Some(suffix) = integer.suffix {
+        get_or_add_type_integer(type_cache, suffix.size, suffix.signed)
+    } else if suggested_type
+        .as_ref()
+        .and_then(|typ| typ.as_integer())
+        .is_some()
+    {
+        suggested_type.unwrap()
+    } else if integer.character {
+        get_or_add_type_integer(type_cache, 1, false)
+    } else {
+        get_or_add_type_integer(type_cache, 8, true)
+    };
+    let (&size, &signed) = typ.as_integer().unwrap();
+    let bits = size as u32 * 8;
+    let max = match (signed, negated) {
+        (true, false) => (1 << (bits - 1)) - 1,
+        (true, true) => 1 << (bits - 1),
+        (false, false) => u128::MAX >> (128 - bits),
+        (false, true) => 0,
+    };
+    if integer.value > max {
+        return Err(ResolvingError::IntegerDoesNotFit {
+            value: integer.value,
+            negated,
+            typ,
+            location: integer.location,
+        });
+    }
+    *integer.resolved_type.borrow_mut() = Some(typ);
+    Ok(())
+}
+
 pub fn resolve(
     ast: &Ast,
     suggested_type: Option<Rc<Type>>,
@@ -367,7 +980,9 @@ pub fn resolve(
         typ
     } else {
         if ast.get_resolving() {
-            todo!("cyclic dependency found")
+            return Err(ResolvingError::CyclicDependency {
+                location: ast.get_location(),
+            });
         }
         ast.set_resolving(true);
         match ast {
@@ -380,15 +995,24 @@ pub fn resolve(
                     resolve(&ast, None, defered_asts, &parent_procedure, type_cache)?;
                 }
             }
+            Ast::Import(import) => {
+                *import.resolved_type.borrow_mut() = Some(get_or_add_type_void(type_cache));
+                resolve(
+                    &Ast::File(import.file.clone()),
+                    None,
+                    defered_asts,
+                    &None,
+                    type_cache,
+                )?;
+            }
             Ast::Procedure(procedure) => {
-                let suggested_proc_type = suggested_type
-                    .as_ref()
-                    .map(|typ| typ.as_procedure())
-                    .flatten();
+                let suggested_proc_type =
+                    suggested_type.as_ref().and_then(|typ| typ.as_procedure());
                 let mut parameter_types = vec![];
                 for (i, parameter) in procedure.parameters.iter().enumerate() {
-                    let suggested_parameter_type =
-                        suggested_proc_type.map(|(parameters, _, _)| parameters[i].clone());
+                    let suggested_parameter_type = suggested_proc_type
+                        .and_then(|(parameters, _, _)| parameters.get(i))
+                        .cloned();
                     parameter_types.push(resolve(
                         &Ast::Parameter(parameter.clone()),
                         suggested_parameter_type,
@@ -409,10 +1033,7 @@ pub fn resolve(
                     &get_or_add_type_type(type_cache),
                     procedure.return_type.get_location(),
                 )?;
-                let return_type = eval(&procedure.return_type, type_cache)
-                    .as_type()
-                    .unwrap()
-                    .clone();
+                let return_type = eval_type(&procedure.return_type, type_cache)?;
                 *procedure.resolved_type.borrow_mut() = Some(get_or_add_type_procedure(
                     type_cache,
                     parameter_types,
@@ -425,6 +1046,7 @@ pub fn resolve(
                         fn does_return(ast: &Ast) -> bool {
                             match ast {
                                 Ast::File(file) => file.expressions.iter().any(does_return),
+                                Ast::Import(_) => false,
                                 Ast::Procedure(_) => false,
                                 Ast::ProcedureType(_) => false,
                                 Ast::Parameter(_) => false,
@@ -432,7 +1054,9 @@ pub fn resolve(
                                 Ast::LetDeclaration(declaration) => does_return(&declaration.value),
                                 Ast::VarDeclaration(declaration) => does_return(&declaration.value),
                                 Ast::Name(_) => false,
+                                Ast::MemberAccess(_) => false,
                                 Ast::Integer(_) => false,
+                                Ast::String(_) => false,
                                 Ast::Call(call) => {
                                     does_return(&call.operand)
                                         || call.arguments.iter().any(does_return)
@@ -462,6 +1086,23 @@ pub fn resolve(
                                 }
                                 Ast::Cast(cast) => does_return(&cast.operand),
                                 Ast::Builtin(_) => false,
+                                Ast::StructType(_) => false,
+                                Ast::StructLiteral(literal) => {
+                                    literal.fields.iter().any(|field| does_return(&field.value))
+                                }
+                                Ast::ArrayType(_) => false,
+                                Ast::ArrayLiteral(literal) => {
+                                    literal.elements.iter().any(does_return)
+                                }
+                                Ast::Index(index) => {
+                                    does_return(&index.operand) || does_return(&index.index)
+                                }
+                                Ast::SliceType(_) => false,
+                                Ast::Slice(slice) => {
+                                    does_return(&slice.operand)
+                                        || does_return(&slice.start)
+                                        || does_return(&slice.end)
+                                }
                             }
                         }
                         let scope = Ast::Scope(scope.clone());
@@ -477,7 +1118,7 @@ pub fn resolve(
             Ast::ProcedureType(procedure_type) => {
                 *procedure_type.resolved_type.borrow_mut() = Some(get_or_add_type_type(type_cache));
                 for parameter in &procedure_type.parameter_types {
-                    resolve(&parameter, None, defered_asts, &None, type_cache)?;
+                    resolve(parameter, None, defered_asts, &None, type_cache)?;
                 }
                 let return_type_type = resolve(
                     &procedure_type.return_type,
@@ -506,7 +1147,7 @@ pub fn resolve(
                     parameter.typ.get_location(),
                 )?;
                 *parameter.resolved_type.borrow_mut() =
-                    Some(eval(&parameter.typ, type_cache).as_type().unwrap().clone());
+                    Some(eval_type(&parameter.typ, type_cache)?);
             }
             Ast::Scope(scope) => {
                 *scope.resolved_type.borrow_mut() = Some(get_or_add_type_void(type_cache));
@@ -528,7 +1169,7 @@ pub fn resolve(
                         &get_or_add_type_type(type_cache),
                         typ.get_location(),
                     )?;
-                    let resolved_type = eval(typ, type_cache).as_type().unwrap().clone();
+                    let resolved_type = eval_type(typ, type_cache)?;
                     *declaration.resolved_type.borrow_mut() = Some(resolved_type.clone());
                     Some(resolved_type)
                 } else {
@@ -546,7 +1187,7 @@ pub fn resolve(
                 } else {
                     expect_type(
                         &value_type,
-                        &declaration.resolved_type.borrow().as_ref().unwrap(),
+                        declaration.resolved_type.borrow().as_ref().unwrap(),
                         declaration.value.get_location(),
                     )?;
                 }
@@ -565,7 +1206,7 @@ pub fn resolve(
                         &get_or_add_type_type(type_cache),
                         typ.get_location(),
                     )?;
-                    let resolved_type = eval(typ, type_cache).as_type().unwrap().clone();
+                    let resolved_type = eval_type(typ, type_cache)?;
                     *declaration.resolved_type.borrow_mut() = Some(resolved_type.clone());
                     Some(resolved_type)
                 } else {
@@ -583,7 +1224,7 @@ pub fn resolve(
                 } else {
                     expect_type(
                         &value_type,
-                        &declaration.resolved_type.borrow().as_ref().unwrap(),
+                        declaration.resolved_type.borrow().as_ref().unwrap(),
                         declaration.value.get_location(),
                     )?;
                 }
@@ -594,27 +1235,60 @@ pub fn resolve(
                     .as_ref()
                     .expect("the name should be resolved at this point");
                 resolve(
-                    &declaration,
+                    declaration,
                     suggested_type,
                     defered_asts,
                     parent_procedure,
                     type_cache,
                 )?;
             }
+            Ast::MemberAccess(member_access) => {
+                let declaration = member_access.resolved_declaration.borrow().clone();
+                if let Some(declaration) = declaration {
+                    resolve(
+                        &declaration,
+                        suggested_type,
+                        defered_asts,
+                        parent_procedure,
+                        type_cache,
+                    )?;
+                } else {
+                    let operand_type = resolve(
+                        &member_access.operand,
+                        None,
+                        defered_asts,
+                        parent_procedure,
+                        type_cache,
+                    )?;
+                    // fields are accessed through a pointer the same way
+                    let struct_type = operand_type.as_pointer().unwrap_or(&operand_type);
+                    let has_length =
+                        struct_type.as_slice().is_some() || struct_type.as_array().is_some();
+                    let field_type = struct_type
+                        .as_struct()
+                        .and_then(|fields| {
+                            fields.iter().find(|(name, _)| *name == member_access.name)
+                        })
+                        .map(|(_, typ)| typ.clone())
+                        .or_else(|| {
+                            (has_length && member_access.name == "len")
+                                .then(|| get_or_add_type_integer(type_cache, 8, false))
+                        })
+                        .ok_or_else(|| ResolvingError::UnknownField {
+                            name: member_access.name.clone(),
+                            typ: operand_type.clone(),
+                            location: member_access.location,
+                        })?;
+                    *member_access.resolved_type.borrow_mut() = Some(field_type);
+                }
+            }
             Ast::Integer(integer) => {
-                // TODO: integer size check
-                *integer.resolved_type.borrow_mut() = Some(
-                    if suggested_type
-                        .as_ref()
-                        .map(|typ| typ.as_integer())
-                        .flatten()
-                        .is_some()
-                    {
-                        suggested_type.unwrap().clone()
-                    } else {
-                        get_or_add_type_integer(type_cache, 8, true)
-                    },
-                );
+                resolve_integer(integer, false, suggested_type, type_cache)?;
+            }
+            Ast::String(string) => {
+                let u8_type = get_or_add_type_integer(type_cache, 1, false);
+                *string.resolved_type.borrow_mut() =
+                    Some(get_or_add_type_slice(type_cache, u8_type));
             }
             Ast::Call(call) => {
                 let operand_type = resolve(
@@ -628,11 +1302,18 @@ pub fn resolve(
                     if let Some(procedure_type) = operand_type.as_procedure() {
                         procedure_type
                     } else {
-                        todo!("error")
+                        return Err(ResolvingError::NotCallable {
+                            typ: operand_type.clone(),
+                            location: call.operand.get_location(),
+                        });
                     };
                 *call.resolved_type.borrow_mut() = Some(return_type.clone());
                 if call.arguments.len() != parameter_types.len() {
-                    todo!("error")
+                    return Err(ResolvingError::WrongArgumentCount {
+                        expected: parameter_types.len(),
+                        got: call.arguments.len(),
+                        location: call.location,
+                    });
                 }
                 for (argument, expected_argument_type) in
                     call.arguments.iter().zip(parameter_types.iter())
@@ -656,7 +1337,9 @@ pub fn resolve(
                 let procedure = if let Some(procedure) = parent_procedure {
                     procedure
                 } else {
-                    todo!("cannot use return outside of a function")
+                    return Err(ResolvingError::ReturnOutsideProcedure {
+                        location: returnn.location,
+                    });
                 };
                 let return_type = Ast::Procedure(procedure.clone())
                     .get_type()
@@ -678,7 +1361,7 @@ pub fn resolve(
                     expect_type(
                         &get_or_add_type_void(type_cache),
                         &return_type,
-                        returnn.location.clone(),
+                        returnn.location,
                     )?;
                 }
             }
@@ -692,11 +1375,20 @@ pub fn resolve(
                         type_cache,
                     )?;
                     if operand_type.as_integer().is_none() {
-                        todo!()
+                        return Err(ResolvingError::ExpectedIntegerType {
+                            got: operand_type,
+                            location: unary.operand.get_location(),
+                        });
                     }
                     *unary.resolved_type.borrow_mut() = Some(operand_type);
                 }
                 UnaryOperator::Negation => {
+                    // the literal alone may not fit, like the 128 in -128s8
+                    if let Ast::Integer(integer) = &unary.operand {
+                        if integer.resolved_type.borrow().is_none() {
+                            resolve_integer(integer, true, suggested_type.clone(), type_cache)?;
+                        }
+                    }
                     let operand_type = resolve(
                         &unary.operand,
                         suggested_type,
@@ -705,7 +1397,10 @@ pub fn resolve(
                         type_cache,
                     )?;
                     if operand_type.as_integer().is_none() {
-                        todo!()
+                        return Err(ResolvingError::ExpectedIntegerType {
+                            got: operand_type,
+                            location: unary.operand.get_location(),
+                        });
                     }
                     *unary.resolved_type.borrow_mut() = Some(operand_type);
                 }
@@ -748,7 +1443,9 @@ pub fn resolve(
                         type_cache,
                     )?;
                     if !is_addressable(&unary.operand) {
-                        todo!()
+                        return Err(ResolvingError::NotAddressable {
+                            location: unary.operand.get_location(),
+                        });
                     }
                     *unary.resolved_type.borrow_mut() =
                         Some(get_or_add_type_pointer(type_cache, operand_type));
@@ -764,7 +1461,10 @@ pub fn resolve(
                     let typ = if let Some(typ) = operand_type.as_pointer() {
                         typ.clone()
                     } else {
-                        todo!()
+                        return Err(ResolvingError::ExpectedPointerType {
+                            got: operand_type,
+                            location: unary.operand.get_location(),
+                        });
                     };
                     *unary.resolved_type.borrow_mut() = Some(typ);
                 }
@@ -791,13 +1491,25 @@ pub fn resolve(
                     | BinaryOperator::Divide
                     | BinaryOperator::Remainder => {
                         if left_type.as_integer().is_none() {
-                            todo!()
+                            return Err(ResolvingError::ExpectedIntegerType {
+                                got: left_type,
+                                location: binary.left.get_location(),
+                            });
                         }
-                        expect_type(&right_type, &left_type, binary.location.clone())?;
+                        expect_type(&right_type, &left_type, binary.location)?;
                         *binary.resolved_type.borrow_mut() = Some(left_type);
                     }
                     BinaryOperator::Equal | BinaryOperator::NotEqual => {
-                        expect_type(&right_type, &left_type, binary.location.clone())?;
+                        if left_type.as_struct().is_some()
+                            || left_type.as_array().is_some()
+                            || left_type.as_slice().is_some()
+                        {
+                            return Err(ResolvingError::NotComparable {
+                                typ: left_type,
+                                location: binary.location,
+                            });
+                        }
+                        expect_type(&right_type, &left_type, binary.location)?;
                         *binary.resolved_type.borrow_mut() = Some(get_or_add_type_bool(type_cache));
                     }
                     BinaryOperator::LessThan
@@ -805,9 +1517,12 @@ pub fn resolve(
                     | BinaryOperator::LessThanEqual
                     | BinaryOperator::GreaterThanEqual => {
                         if left_type.as_integer().is_none() {
-                            todo!()
+                            return Err(ResolvingError::ExpectedIntegerType {
+                                got: left_type,
+                                location: binary.left.get_location(),
+                            });
                         }
-                        expect_type(&right_type, &left_type, binary.location.clone())?;
+                        expect_type(&right_type, &left_type, binary.location)?;
                         *binary.resolved_type.borrow_mut() = Some(get_or_add_type_bool(type_cache));
                     }
                 }
@@ -834,7 +1549,7 @@ pub fn resolve(
                 )?;
                 if let Some(else_expression) = &iff.else_expression {
                     let else_type = resolve(
-                        &else_expression,
+                        else_expression,
                         suggested_type,
                         defered_asts,
                         parent_procedure,
@@ -890,7 +1605,7 @@ pub fn resolve(
                     &get_or_add_type_type(type_cache),
                     cast.typ.get_location(),
                 )?;
-                let typ = eval(&cast.typ, type_cache).as_type().unwrap().clone();
+                let typ = eval_type(&cast.typ, type_cache)?;
                 let operand_type = resolve(
                     &cast.operand,
                     typ.clone().into(),
@@ -898,10 +1613,30 @@ pub fn resolve(
                     parent_procedure,
                     type_cache,
                 )?;
-                if operand_type != typ
-                    && !(operand_type.as_integer().is_some() && typ.as_integer().is_some())
-                {
-                    todo!()
+                // arrays, pointers to them and slices convert to a pointer to their first element
+                let decays = |from: &Rc<Type>| {
+                    from.as_array()
+                        .is_some_and(|(_, element_type)| typ.as_pointer() == Some(element_type))
+                };
+                let slice_decays = operand_type
+                    .as_slice()
+                    .is_some_and(|element_type| typ.as_pointer() == Some(element_type));
+                if decays(&operand_type) && !is_addressable(&cast.operand) {
+                    return Err(ResolvingError::NotAddressable {
+                        location: cast.operand.get_location(),
+                    });
+                }
+                let valid = operand_type == typ
+                    || (operand_type.as_integer().is_some() && typ.as_integer().is_some())
+                    || decays(&operand_type)
+                    || operand_type.as_pointer().is_some_and(decays)
+                    || slice_decays;
+                if !valid {
+                    return Err(ResolvingError::InvalidCast {
+                        from: operand_type,
+                        to: typ,
+                        location: cast.location,
+                    });
                 }
                 *cast.resolved_type.borrow_mut() = Some(typ);
             }
@@ -959,11 +1694,637 @@ pub fn resolve(
                     &AstBuiltinKind::IntegerType { size, signed } => {
                         get_or_add_type_integer(type_cache, size, signed)
                     }
+                    AstBuiltinKind::String => {
+                        let u8_type = get_or_add_type_integer(type_cache, 1, false);
+                        get_or_add_type_slice(type_cache, u8_type)
+                    }
                 });
             }
+            Ast::StructType(struct_type) => {
+                *struct_type.resolved_type.borrow_mut() = Some(get_or_add_type_type(type_cache));
+                for (i, field) in struct_type.fields.iter().enumerate() {
+                    check_duplicate_field(&struct_type.fields[..i], field)?;
+                    let field_type_type = resolve(
+                        &field.value,
+                        Some(get_or_add_type_type(type_cache)),
+                        defered_asts,
+                        &None,
+                        type_cache,
+                    )?;
+                    expect_type(
+                        &field_type_type,
+                        &get_or_add_type_type(type_cache),
+                        field.value.get_location(),
+                    )?;
+                }
+                *struct_type.typ.borrow_mut() = Some(eval_type(ast, type_cache)?);
+            }
+            Ast::StructLiteral(literal) => {
+                let type_type = resolve(
+                    &literal.typ,
+                    Some(get_or_add_type_type(type_cache)),
+                    defered_asts,
+                    parent_procedure,
+                    type_cache,
+                )?;
+                expect_type(
+                    &type_type,
+                    &get_or_add_type_type(type_cache),
+                    literal.typ.get_location(),
+                )?;
+                let typ = eval_type(&literal.typ, type_cache)?;
+                let fields = typ
+                    .as_struct()
+                    .ok_or_else(|| ResolvingError::ExpectedStructType {
+                        got: typ.clone(),
+                        location: literal.typ.get_location(),
+                    })?;
+                for (i, field) in literal.fields.iter().enumerate() {
+                    check_duplicate_field(&literal.fields[..i], field)?;
+                    let field_type = fields
+                        .iter()
+                        .find(|(name, _)| *name == field.name)
+                        .map(|(_, typ)| typ.clone())
+                        .ok_or_else(|| ResolvingError::UnknownField {
+                            name: field.name.clone(),
+                            typ: typ.clone(),
+                            location: field.location,
+                        })?;
+                    let value_type = resolve(
+                        &field.value,
+                        Some(field_type.clone()),
+                        defered_asts,
+                        parent_procedure,
+                        type_cache,
+                    )?;
+                    expect_type(&value_type, &field_type, field.value.get_location())?;
+                }
+                if let Some((name, field_type)) = fields
+                    .iter()
+                    .find(|(name, _)| !literal.fields.iter().any(|field| field.name == *name))
+                {
+                    return Err(ResolvingError::MissingField {
+                        name: name.clone(),
+                        typ: field_type.clone(),
+                        location: literal.location,
+                    });
+                }
+                *literal.resolved_type.borrow_mut() = Some(typ.clone());
+            }
+            Ast::ArrayType(array_type) => {
+                *array_type.resolved_type.borrow_mut() = Some(get_or_add_type_type(type_cache));
+                let length_type = resolve(
+                    &array_type.length,
+                    Some(get_or_add_type_integer(type_cache, 8, false)),
+                    defered_asts,
+                    &None,
+                    type_cache,
+                )?;
+                if length_type.as_integer().is_none() {
+                    return Err(ResolvingError::ExpectedIntegerType {
+                        got: length_type,
+                        location: array_type.length.get_location(),
+                    });
+                }
+                let element_type_type = resolve(
+                    &array_type.element_type,
+                    Some(get_or_add_type_type(type_cache)),
+                    defered_asts,
+                    &None,
+                    type_cache,
+                )?;
+                expect_type(
+                    &element_type_type,
+                    &get_or_add_type_type(type_cache),
+                    array_type.element_type.get_location(),
+                )?;
+                if is_constant(&array_type.length) {
+                    let length = eval(&array_type.length, type_cache).to_integer().unwrap();
+                    // no object can be larger than isize::MAX bytes
+                    if usize::try_from(length).map_or(true, |length| length > isize::MAX as usize) {
+                        return Err(ResolvingError::ArrayTooLong {
+                            length,
+                            location: array_type.length.get_location(),
+                        });
+                    }
+                }
+                *array_type.typ.borrow_mut() = Some(eval_type(ast, type_cache)?);
+            }
+            Ast::ArrayLiteral(literal) => {
+                let mut element_type = suggested_type
+                    .as_ref()
+                    .and_then(|typ| typ.as_array())
+                    .map(|(_, element_type)| element_type.clone());
+                for element in &literal.elements {
+                    let typ = resolve(
+                        element,
+                        element_type.clone(),
+                        defered_asts,
+                        parent_procedure,
+                        type_cache,
+                    )?;
+                    if let Some(element_type) = &element_type {
+                        expect_type(&typ, element_type, element.get_location())?;
+                    } else {
+                        element_type = Some(typ);
+                    }
+                }
+                let element_type = element_type.ok_or(ResolvingError::UntypedEmptyArray {
+                    location: literal.location,
+                })?;
+                *literal.resolved_type.borrow_mut() = Some(get_or_add_type_array(
+                    type_cache,
+                    literal.elements.len(),
+                    element_type,
+                ));
+            }
+            Ast::Index(index) => {
+                let operand_type = resolve(
+                    &index.operand,
+                    None,
+                    defered_asts,
+                    parent_procedure,
+                    type_cache,
+                )?;
+                // elements are indexed through a pointer the same way
+                let indexed_type = operand_type.as_pointer().unwrap_or(&operand_type);
+                let (length, element_type) = match &**indexed_type {
+                    Type::Array {
+                        length,
+                        element_type,
+                    } => (Some(*length), element_type.clone()),
+                    Type::Slice { element_type } => (None, element_type.clone()),
+                    _ => {
+                        return Err(ResolvingError::NotIndexable {
+                            typ: operand_type.clone(),
+                            location: index.operand.get_location(),
+                        })
+                    }
+                };
+                let index_type = resolve(
+                    &index.index,
+                    Some(get_or_add_type_integer(type_cache, 8, false)),
+                    defered_asts,
+                    parent_procedure,
+                    type_cache,
+                )?;
+                if index_type.as_integer().is_none() {
+                    return Err(ResolvingError::ExpectedIntegerType {
+                        got: index_type,
+                        location: index.index.get_location(),
+                    });
+                }
+                if let (Ast::Integer(integer), Some(length)) = (&index.index, length) {
+                    if integer.value >= length as u128 {
+                        return Err(ResolvingError::IndexOutOfBounds {
+                            index: integer.value,
+                            length,
+                            location: index.index.get_location(),
+                        });
+                    }
+                }
+                *index.resolved_type.borrow_mut() = Some(element_type);
+            }
+            Ast::SliceType(slice_type) => {
+                *slice_type.resolved_type.borrow_mut() = Some(get_or_add_type_type(type_cache));
+                let element_type_type = resolve(
+                    &slice_type.element_type,
+                    Some(get_or_add_type_type(type_cache)),
+                    defered_asts,
+                    &None,
+                    type_cache,
+                )?;
+                expect_type(
+                    &element_type_type,
+                    &get_or_add_type_type(type_cache),
+                    slice_type.element_type.get_location(),
+                )?;
+                *slice_type.typ.borrow_mut() = Some(eval_type(ast, type_cache)?);
+            }
+            Ast::Slice(slice) => {
+                let operand_type = resolve(
+                    &slice.operand,
+                    None,
+                    defered_asts,
+                    parent_procedure,
+                    type_cache,
+                )?;
+                let sliced_type = operand_type.as_pointer().unwrap_or(&operand_type);
+                let (length, element_type) = match &**sliced_type {
+                    Type::Array {
+                        length,
+                        element_type,
+                    } => (Some(*length), element_type.clone()),
+                    Type::Slice { element_type } => (None, element_type.clone()),
+                    _ => {
+                        return Err(ResolvingError::NotIndexable {
+                            typ: operand_type.clone(),
+                            location: slice.operand.get_location(),
+                        })
+                    }
+                };
+                // the slice points into the array, so it cannot be a temporary copy
+                if operand_type.as_array().is_some() && !is_addressable(&slice.operand) {
+                    return Err(ResolvingError::NotAddressable {
+                        location: slice.operand.get_location(),
+                    });
+                }
+                for bound in [&slice.start, &slice.end] {
+                    let bound_type = resolve(
+                        bound,
+                        Some(get_or_add_type_integer(type_cache, 8, false)),
+                        defered_asts,
+                        parent_procedure,
+                        type_cache,
+                    )?;
+                    if bound_type.as_integer().is_none() {
+                        return Err(ResolvingError::ExpectedIntegerType {
+                            got: bound_type,
+                            location: bound.get_location(),
+                        });
+                    }
+                    if let (Ast::Integer(integer), Some(length)) = (bound, length) {
+                        if integer.value > length as u128 {
+                            return Err(ResolvingError::IndexOutOfBounds {
+                                index: integer.value,
+                                length,
+                                location: bound.get_location(),
+                            });
+                        }
+                    }
+                }
+                *slice.resolved_type.borrow_mut() =
+                    Some(get_or_add_type_slice(type_cache, element_type));
+            }
         }
         ast.set_resolving(false);
         ast.get_type()
             .unwrap_or_else(|| panic!("type of ast should have been resolved"))
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{CompileError, Compiler, MemoryLoader, ResolvingError};
+
+    type IsExpectedError = fn(&ResolvingError) -> bool;
+
+    fn resolving_error(source: &str) -> ResolvingError {
+        match Compiler::new().compile_source("invalid.lang", source) {
+            Err(CompileError::Resolving(error)) => error,
+            Err(error) => panic!("expected a resolving error, but got: {error}"),
+            Ok(_) => panic!("expected a resolving error, but the program compiled"),
+        }
+    }
+
+    #[test]
+    fn invalid_programs() {
+        let programs: &[(&str, IsExpectedError)] = &[
+            ("let x = y\n", ResolvingError::is_undeclared_name),
+            ("var x <- 1\nvar x <- 2\n", ResolvingError::is_redeclaration),
+            ("var x: bool <- 5\n", ResolvingError::is_expected_type),
+            (
+                "proc f() => u64 {}\n",
+                ResolvingError::is_procedure_no_return,
+            ),
+            ("let x = 5\nx <- 6\n", ResolvingError::is_not_assignable),
+            ("let x = 5\n&x\n", ResolvingError::is_not_addressable),
+            (
+                "proc f(x: f) => void {}\n",
+                ResolvingError::is_cyclic_dependency,
+            ),
+            (
+                "proc f() => type {\n    return u64\n}\nvar x: f() <- 1\n",
+                ResolvingError::is_not_constant,
+            ),
+            ("let x = 5\nx()\n", ResolvingError::is_not_callable),
+            (
+                "proc f(a: u64) => void {}\nf()\n",
+                ResolvingError::is_wrong_argument_count,
+            ),
+            (
+                "proc f(a: u64, b: u64) => void {}\nlet g: proc(u64) => void = f\n",
+                ResolvingError::is_expected_type,
+            ),
+            ("return 5\n", ResolvingError::is_return_outside_procedure),
+            (
+                "let b = 1 == 1\n-b\n",
+                ResolvingError::is_expected_integer_type,
+            ),
+            (
+                "let b = 1 == 1\nb + b\n",
+                ResolvingError::is_expected_integer_type,
+            ),
+            (
+                "let b = 1 == 1\nb < b\n",
+                ResolvingError::is_expected_integer_type,
+            ),
+            ("let x = 5\nx^\n", ResolvingError::is_expected_pointer_type),
+            ("cast(bool) 5\n", ResolvingError::is_invalid_cast),
+            (
+                "var x: u8 <- 256\n",
+                ResolvingError::is_integer_does_not_fit,
+            ),
+            ("var x <- 128s8\n", ResolvingError::is_integer_does_not_fit),
+            ("var x <- -1u32\n", ResolvingError::is_integer_does_not_fit),
+            ("var x: u8 <- 1u16\n", ResolvingError::is_expected_type),
+            ("u8.{}\n", ResolvingError::is_expected_struct_type),
+            (
+                "let p = struct { x: u8 }.{ x = 1 }\nproc f() => u8 {\n    return p.x\n}\n",
+                ResolvingError::is_undeclared_name,
+            ),
+            (
+                "let P = struct { x: u8 }\nP.{ x = 1 }.y\n",
+                ResolvingError::is_unknown_field,
+            ),
+            (
+                "let P = struct { x: u8 }\nP.{ x = 1, y = 2 }\n",
+                ResolvingError::is_unknown_field,
+            ),
+            (
+                "let P = struct { x: u8, y: u8 }\nP.{ x = 1 }\n",
+                ResolvingError::is_missing_field,
+            ),
+            (
+                "let P = struct { x: u8 }\nP.{ x = 1, x = 2 }\n",
+                ResolvingError::is_duplicate_field,
+            ),
+            (
+                "struct { x: u8, x: u8 }\n",
+                ResolvingError::is_duplicate_field,
+            ),
+            (
+                "let P = struct { x: u8 }\nP.{ x = 1u16 }\n",
+                ResolvingError::is_expected_type,
+            ),
+            (
+                "let P = struct { x: u8 }\nlet p = P.{ x = 1 }\np.x <- 2\n",
+                ResolvingError::is_not_assignable,
+            ),
+            (
+                "let P = struct { x: u8 }\nP.{ x = 1 } == P.{ x = 1 }\n",
+                ResolvingError::is_not_comparable,
+            ),
+            // struct types with the same fields are still different types
+            (
+                "let P = struct { x: u8 }\nlet Q = struct { x: u8 }\nvar q: Q <- P.{ x = 1 }\n",
+                ResolvingError::is_expected_type,
+            ),
+            ("let x = 5\nx[0]\n", ResolvingError::is_not_indexable),
+            (
+                "var a <- .[1u8, 2]\na[2]\n",
+                ResolvingError::is_index_out_of_bounds,
+            ),
+            ("var a <- .[]\n", ResolvingError::is_untyped_empty_array),
+            (
+                "let A = [0xFFFF_FFFF_FFFF_FFFF]u8\n",
+                ResolvingError::is_array_too_long,
+            ),
+            (
+                "var a: [2]u8 <- .[1, 2, 3]\n",
+                ResolvingError::is_expected_type,
+            ),
+            ("var a <- .[1u8, 1u16]\n", ResolvingError::is_expected_type),
+            (
+                "var a <- .[1u8]\ncast(^u16) a\n",
+                ResolvingError::is_invalid_cast,
+            ),
+            (
+                "let a = .[1u8]\ncast(^u8) a\n",
+                ResolvingError::is_not_addressable,
+            ),
+            (
+                "var a <- .[1u8]\na == a\n",
+                ResolvingError::is_not_comparable,
+            ),
+            (
+                "var a <- .[1u8, 2]\na[1..3]\n",
+                ResolvingError::is_index_out_of_bounds,
+            ),
+            (
+                "let a = .[1u8, 2]\na[0..1]\n",
+                ResolvingError::is_not_addressable,
+            ),
+            ("let x = 5\nx[0..1]\n", ResolvingError::is_not_indexable),
+            (
+                "var a <- .[1u8, 2]\nlet s = a[0..2]\ns.len <- 1\n",
+                ResolvingError::is_not_assignable,
+            ),
+            (
+                "var a <- .[1u8, 2]\na[0..2].size\n",
+                ResolvingError::is_unknown_field,
+            ),
+            (
+                "var a <- .[1u8, 2]\nlet s: []u16 = a[0..2]\n",
+                ResolvingError::is_expected_type,
+            ),
+            ("\"abc\" <- \"def\"\n", ResolvingError::is_not_assignable),
+            (
+                "var s: [3]u8 <- \"abc\"\n",
+                ResolvingError::is_expected_type,
+            ),
+            (
+                "var b: u8 <- \"abc\"[0..1]\n",
+                ResolvingError::is_expected_type,
+            ),
+            (
+                "var c <- '\\u{1F600}'u16\n",
+                ResolvingError::is_integer_does_not_fit,
+            ),
+            (
+                "var c: s64 <- 'a'\nvar d: u8 <- c\n",
+                ResolvingError::is_expected_type,
+            ),
+        ];
+        for (source, is_expected_error) in programs {
+            let error = resolving_error(source);
+            assert!(
+                is_expected_error(&error),
+                "unexpected error for program:\n{source}\n{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn structs() {
+        let source = "\
+let Point = struct { x: s32, y: s32 }
+let scale: s32 = 2
+proc scaled(p: Point) => Point {
+    var q: Point <- p
+    let ptr = &q
+    ptr.x <- p.x * scale
+    return q
+}
+var p: Point <- scaled(Point.{ y = 2, x = 1 })
+";
+        let output = Compiler::new()
+            .compile_source("structs.lang", source)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // named types and other constants are visible inside procedures
+        assert!(output.contains("_scaled(_struct"));
+        assert!(output.contains("= &(s32){2};"));
+        assert!(output.contains(")->_x;"));
+    }
+
+    #[test]
+    fn arrays() {
+        let source = "\
+var a: [3]u8 <- .[1, 2, 3]
+var grid <- .[.[1s32, 2], .[3, 4]]
+let ptr = &a
+ptr[0] <- a[2]
+let first = cast(^u8) a
+";
+        let output = Compiler::new()
+            .compile_source("arrays.lang", source)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("u8 elements[3];"));
+        assert!(output.contains("s32 elements[2];"));
+        // indexing through a pointer and decaying to a pointer to the first element
+        assert!(output.contains(")->elements[*_"));
+        assert!(output.contains("->elements};"));
+    }
+
+    #[test]
+    fn slices() {
+        let source = "\
+var a: [4]u8 <- .[1, 2, 3, 4]
+var i: s64 <- 1
+let s: []u8 = a[i..a.len]
+s[i] <- 0
+";
+        let output = Compiler::new()
+            .set_bounds_checks(true)
+            .compile_source("slices.lang", source)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("#define LANGITE_BOUNDS_CHECKS\n"));
+        assert!(output.contains(
+            "#define _bounds_check(condition) do { if (!(condition)) __builtin_trap(); } while (0)"
+        ));
+        assert!(output.contains("u8 *elements;\nu64 length;"));
+        // a negative index must not pass as smaller than the length
+        assert!(output.contains("_bounds_check((u64)*_10 <= (u64)*_12 && (u64)*_12 <= (u64)4);"));
+        assert!(output.contains("_bounds_check((u64)*_16 < (u64)_15->length);"));
+    }
+
+    #[test]
+    fn strings() {
+        let source = "\
+let s: string = \"h\\u{E9}\\n\"
+var length: u64 <- s.len + \"\".len
+var t <- \"abc\"
+t[0] <- 120u8
+";
+        let output = Compiler::new()
+            .compile_source("strings.lang", source)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("static u8 _1_data[] = {104, 195, 169, 10, 0};"));
+        assert!(output.contains("{_1_data, 4};"));
+        assert!(output.contains("static u8 _5_data[] = {0};"));
+        // string data is writable, so writing through a slice of a literal does not crash
+        assert!(!output.contains("const"));
+    }
+
+    #[test]
+    fn characters() {
+        let source = "\
+var c <- 'a'
+let digit: u64 = 7 + '0'
+let smile = '\\u{1F600}'u32
+let negative: s32 = -'\\n'
+";
+        let output = Compiler::new()
+            .compile_source("characters.lang", source)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // a byte unless the context or a suffix asks for another integer type
+        assert!(output.contains("u8 *_1 = &(u8){97};"));
+        assert!(output.contains("u64 *_4 = &(u64){48};"));
+        assert!(output.contains("u32 *_7 = &(u32){128512};"));
+        assert!(output.contains("s32 *_9 = &(s32){10};"));
+    }
+
+    #[test]
+    fn integer_literal_limits() {
+        let source = "var a <- -128s8\nvar b: u8 <- 255\nvar c <- 0xFFFF_FFFF_FFFF_FFFFu64\n";
+        Compiler::new()
+            .compile_source("limits.lang", source)
+            .unwrap();
+    }
+
+    #[test]
+    fn modules() {
+        let mut loader = MemoryLoader::new();
+        loader
+            .add_file(
+                "a.lang",
+                "pub proc hello() => u64 {\n    return 1\n}\npub var count: u64 <- 2\n\
+                 let secret = 3\n",
+            )
+            .add_file("b.lang", "pub proc hello() => u64 {\n    return 3\n}\n");
+        let compile = |source: &str| {
+            let mut compiler = Compiler::new();
+            compiler.set_loader(loader.clone());
+            compiler.compile_source("main.lang", source)
+        };
+
+        compile(
+            "#import \"a.lang\" as a\n#import \"b.lang\" as b\n#import \"a.lang\" (hello)\n\
+             #import \"a.lang\"\n\
+             a.hello() + b.hello() + hello() + a.count\n\
+             let secret = 4\n\
+             proc f() => u64 {\n    return a.hello()\n}\n",
+        )
+        .unwrap();
+
+        let programs: &[(&str, IsExpectedError)] = &[
+            (
+                "#import \"a.lang\"\n#import \"b.lang\"\n",
+                ResolvingError::is_redeclaration,
+            ),
+            (
+                "#import \"a.lang\" (goodbye)\n",
+                ResolvingError::is_unknown_module_member,
+            ),
+            (
+                "#import \"a.lang\" as a\na.goodbye()\n",
+                ResolvingError::is_unknown_module_member,
+            ),
+            (
+                "#import \"a.lang\" as a\nproc f() => u64 {\n    return a.count\n}\n",
+                ResolvingError::is_unknown_module_member,
+            ),
+            ("let x = 1\nx.y\n", ResolvingError::is_unknown_field),
+            (
+                "#import \"a.lang\"\nsecret\n",
+                ResolvingError::is_private_declaration,
+            ),
+            (
+                "#import \"a.lang\" (secret)\n",
+                ResolvingError::is_private_declaration,
+            ),
+            (
+                "#import \"a.lang\" as a\na.secret\n",
+                ResolvingError::is_private_declaration,
+            ),
+            (
+                "#import \"a.lang\" as a\nlet x = a\n",
+                ResolvingError::is_module_used_as_value,
+            ),
+        ];
+        for (source, is_expected_error) in programs {
+            match compile(source) {
+                Err(CompileError::Resolving(error)) => assert!(
+                    is_expected_error(&error),
+                    "unexpected error for program:\n{source}\n{error}"
+                ),
+                result => panic!("expected a resolving error for program:\n{source}\n{result:?}"),
+            }
+        }
+    }
+}
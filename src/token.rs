@@ -15,6 +15,10 @@ pub enum TokenKind {
     String,
     #[display(fmt = "{{integer}}")]
     Integer,
+    #[display(fmt = "{{character literal}}")]
+    Character,
+    #[display(fmt = "{{doc comment}}")]
+    DocComment,
     #[display(fmt = "(")]
     OpenParenthesis,
     #[display(fmt = ")")]
@@ -23,10 +27,18 @@ pub enum TokenKind {
     OpenBrace,
     #[display(fmt = "}}")]
     CloseBrace,
+    #[display(fmt = "[")]
+    OpenBracket,
+    #[display(fmt = "]")]
+    CloseBracket,
     #[display(fmt = ":")]
     Colon,
     #[display(fmt = ",")]
     Comma,
+    #[display(fmt = ".")]
+    Period,
+    #[display(fmt = "..")]
+    DotDot,
     #[display(fmt = "^")]
     Caret,
     #[display(fmt = "&")]
@@ -87,6 +99,12 @@ pub enum TokenKind {
     WhileKeyword,
     #[display(fmt = "cast")]
     CastKeyword,
+    #[display(fmt = "as")]
+    AsKeyword,
+    #[display(fmt = "pub")]
+    PubKeyword,
+    #[display(fmt = "struct")]
+    StructKeyword,
 }
 
 #[derive(Clone, PartialEq, Debug, Display, EnumAsInner)]
@@ -95,8 +113,26 @@ pub enum TokenData {
     None,
     #[display(fmt = "{:?}", _0)]
     String(String),
-    #[display(fmt = "{}", _0)]
-    Integer(u128),
+    #[display(
+        fmt = "{}{}",
+        _0,
+        "_1.map(|suffix| suffix.to_string()).unwrap_or_default()"
+    )]
+    Integer(u128, Option<IntegerSuffix>),
+    #[display(
+        fmt = "{:?}{}",
+        _0,
+        "_1.map(|suffix| suffix.to_string()).unwrap_or_default()"
+    )]
+    Character(char, Option<IntegerSuffix>),
+}
+
+/// The type written after an integer literal, like the `u8` in `255u8`.
+#[derive(Clone, Copy, PartialEq, Debug, Display)]
+#[display(fmt = "{}{}", "if *signed { \"s\" } else { \"u\" }", "size * 8")]
+pub struct IntegerSuffix {
+    pub size: usize,
+    pub signed: bool,
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -104,4 +140,22 @@ pub struct Token {
     pub kind: TokenKind,
     pub location: SourceSpan,
     pub data: TokenData,
+    /// Only filled in when the lexer keeps trivia.
+    pub leading_trivia: Vec<Trivia>,
+    pub trailing_trivia: Vec<Trivia>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Display)]
+pub enum TriviaKind {
+    /// Spaces and tabs, newlines are tokens.
+    #[display(fmt = "whitespace")]
+    Whitespace,
+    #[display(fmt = "comment")]
+    Comment,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub location: SourceSpan,
 }
@@ -21,11 +21,23 @@ pub enum Type {
     Pointer {
         pointed_to: Rc<Type>,
     },
+    /// Structs are nominal, every struct type expression is a new type even if another one
+    /// has the same fields.
+    Struct {
+        fields: Vec<(String, Rc<Type>)>,
+    },
+    Array {
+        length: usize,
+        element_type: Rc<Type>,
+    },
+    Slice {
+        element_type: Rc<Type>,
+    },
 }
 
 impl PartialEq for Type {
     fn eq(&self, other: &Self) -> bool {
-        self as *const _ == other as *const _
+        std::ptr::eq(self, other)
     }
 }
 
@@ -56,6 +68,24 @@ impl Display for Type {
                 write!(f, "{return_type} {calling_convention}")
             }
             Type::Pointer { pointed_to } => write!(f, "^{pointed_to}"),
+            Type::Struct { fields } => {
+                if fields.is_empty() {
+                    return write!(f, "struct {{}}");
+                }
+                write!(f, "struct {{ ")?;
+                for (i, (name, typ)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {typ}")?;
+                }
+                write!(f, " }}")
+            }
+            Type::Array {
+                length,
+                element_type,
+            } => write!(f, "[{length}]{element_type}"),
+            Type::Slice { element_type } => write!(f, "[]{element_type}"),
         }
     }
 }
@@ -150,3 +180,44 @@ pub fn get_or_add_type_pointer(type_cache: &mut Vec<Rc<Type>>, pointed_to: Rc<Ty
     type_cache.push(typ.clone());
     typ
 }
+
+pub fn add_type_struct(
+    type_cache: &mut Vec<Rc<Type>>,
+    fields: Vec<(String, Rc<Type>)>,
+) -> Rc<Type> {
+    let typ: Rc<_> = Type::Struct { fields }.into();
+    type_cache.push(typ.clone());
+    typ
+}
+
+pub fn get_or_add_type_array(
+    type_cache: &mut Vec<Rc<Type>>,
+    length: usize,
+    element_type: Rc<Type>,
+) -> Rc<Type> {
+    for typ in type_cache.iter() {
+        if let Some((&typ_length, typ_element_type)) = typ.as_array() {
+            if typ_length == length && typ_element_type == &element_type {
+                return typ.clone();
+            }
+        }
+    }
+    let typ: Rc<_> = Type::Array {
+        length,
+        element_type,
+    }
+    .into();
+    type_cache.push(typ.clone());
+    typ
+}
+
+pub fn get_or_add_type_slice(type_cache: &mut Vec<Rc<Type>>, element_type: Rc<Type>) -> Rc<Type> {
+    for typ in type_cache.iter() {
+        if typ.as_slice() == Some(&element_type) {
+            return typ.clone();
+        }
+    }
+    let typ: Rc<_> = Type::Slice { element_type }.into();
+    type_cache.push(typ.clone());
+    typ
+}